@@ -0,0 +1,181 @@
+use crate::parser::Span;
+use chumsky::error::Rich;
+use std::fmt::{Display, Formatter};
+
+mod render;
+mod sources;
+
+pub use render::*;
+pub use sources::*;
+
+/// The code given to every syntax error reported by the parser
+pub const SYNTAX_ERROR: &str = "E0001";
+
+/// How serious a diagnostic is
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Note => write!(f, "note"),
+        }
+    }
+}
+
+/// A span of source that a diagnostic points at, with an optional explanation
+#[derive(Debug, PartialEq, Clone)]
+pub struct Label {
+    pub span: Span,
+    pub message: Option<String>,
+}
+
+impl Label {
+    pub fn new(span: Span) -> Self {
+        Label {
+            span,
+            message: None,
+        }
+    }
+
+    pub fn with_message<T: Into<String>>(mut self, message: T) -> Self {
+        self.message = Some(message.into());
+        self
+    }
+}
+
+/// A problem found in a policy. The primary label marks where the problem is, while
+/// secondary labels point at related source, possibly in other files.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: Option<String>,
+    pub message: String,
+    pub primary: Label,
+    pub secondary: Vec<Label>,
+    pub notes: Vec<String>,
+    pub help: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn new<T: Into<String>>(severity: Severity, message: T, span: Span) -> Self {
+        Diagnostic {
+            severity,
+            code: None,
+            message: message.into(),
+            primary: Label::new(span),
+            secondary: vec![],
+            notes: vec![],
+            help: None,
+        }
+    }
+
+    pub fn error<T: Into<String>>(message: T, span: Span) -> Self {
+        Self::new(Severity::Error, message, span)
+    }
+
+    pub fn warning<T: Into<String>>(message: T, span: Span) -> Self {
+        Self::new(Severity::Warning, message, span)
+    }
+
+    /// The location of the problem
+    pub fn span(&self) -> Span {
+        self.primary.span
+    }
+
+    pub fn with_code<T: Into<String>>(mut self, code: T) -> Self {
+        self.code = Some(code.into());
+        self
+    }
+
+    /// Explain what the primary label points at
+    pub fn with_primary_message<T: Into<String>>(mut self, message: T) -> Self {
+        self.primary.message = Some(message.into());
+        self
+    }
+
+    pub fn with_label(mut self, label: Label) -> Self {
+        self.secondary.push(label);
+        self
+    }
+
+    pub fn with_note<T: Into<String>>(mut self, note: T) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    pub fn with_help<T: Into<String>>(mut self, help: T) -> Self {
+        self.help = Some(help.into());
+        self
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at {}", self.message, self.primary.span)
+    }
+}
+
+impl<'src, T: Display> From<Rich<'src, T, Span>> for Diagnostic {
+    fn from(error: Rich<'src, T, Span>) -> Self {
+        Diagnostic::error(error.reason().to_string(), *error.span()).with_code(SYNTAX_ERROR)
+    }
+}
+
+/// Every diagnostic reported for a policy
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Diagnostics(pub Vec<Diagnostic>);
+
+impl Diagnostics {
+    pub fn iter(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.0.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Whether any diagnostic is an error rather than a warning or note
+    pub fn has_errors(&self) -> bool {
+        self.iter().any(|d| d.severity == Severity::Error)
+    }
+}
+
+impl Display for Diagnostics {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (i, diagnostic) in self.0.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", diagnostic)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Diagnostics {}
+
+impl<'src, T: Display> From<Vec<Rich<'src, T, Span>>> for Diagnostics {
+    fn from(errors: Vec<Rich<'src, T, Span>>) -> Self {
+        Diagnostics(errors.into_iter().map(Diagnostic::from).collect())
+    }
+}
+
+impl IntoIterator for Diagnostics {
+    type Item = Diagnostic;
+    type IntoIter = std::vec::IntoIter<Diagnostic>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
@@ -0,0 +1,272 @@
+use crate::diagnostic::{Diagnostic, Diagnostics, Label, Severity, Sources};
+use ariadne::{CharSet, Color, Config, IndexType, Report, ReportKind};
+use std::fmt::Write;
+use std::ops::Range;
+
+/// How diagnostics are rendered
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Format {
+    /// Annotated source snippets for terminals. Colors are left out when the output is
+    /// not a terminal or `NO_COLOR` is set.
+    Colored,
+    /// Annotated source snippets without colors or box-drawing characters, for logs
+    Plain,
+    /// A JSON array of diagnostics, for tools
+    Json,
+}
+
+impl Diagnostic {
+    /// Renders this diagnostic against the files in `sources`
+    pub fn render(&self, sources: &Sources, format: Format) -> String {
+        match format {
+            Format::Colored => self.report(sources, true),
+            Format::Plain => self.report(sources, false),
+            Format::Json => {
+                let mut json = String::new();
+                self.write_json(sources, &mut json);
+                json
+            }
+        }
+    }
+
+    fn report(&self, sources: &Sources, color: bool) -> String {
+        let span = |label: &Label| -> (String, Range<usize>) {
+            (
+                sources.name(label.span.file).to_string(),
+                label.span.range(),
+            )
+        };
+        let (kind, primary_color) = match self.severity {
+            Severity::Error => (ReportKind::Error, Color::Red),
+            Severity::Warning => (ReportKind::Warning, Color::Yellow),
+            Severity::Note => (ReportKind::Advice, Color::Cyan),
+        };
+        let config = Config::default()
+            .with_color(color)
+            .with_char_set(if color {
+                CharSet::Unicode
+            } else {
+                CharSet::Ascii
+            })
+            .with_index_type(IndexType::Byte);
+
+        let (file, range) = span(&self.primary);
+        let mut report = Report::build(kind, file, range.start)
+            .with_config(config)
+            .with_message(&self.message);
+        if let Some(code) = &self.code {
+            report = report.with_code(code);
+        }
+        let primary = ariadne::Label::new(span(&self.primary))
+            .with_color(primary_color)
+            .with_priority(1);
+        report = report.with_label(match &self.primary.message {
+            Some(message) => primary.with_message(message),
+            None => primary.with_message(&self.message),
+        });
+        for label in &self.secondary {
+            let secondary = ariadne::Label::new(span(label)).with_color(Color::Blue);
+            report = report.with_label(match &label.message {
+                Some(message) => secondary.with_message(message),
+                None => secondary,
+            });
+        }
+        if !self.notes.is_empty() {
+            report = report.with_note(self.notes.join("\n"));
+        }
+        if let Some(help) = &self.help {
+            report = report.with_help(help);
+        }
+
+        let cache = ariadne::sources(
+            sources
+                .files()
+                .map(|(name, source)| (name.clone(), source.clone())),
+        );
+        let mut output = vec![];
+        report
+            .finish()
+            .write(cache, &mut output)
+            .expect("writing to a buffer cannot fail");
+        String::from_utf8_lossy(&output).into_owned()
+    }
+
+    fn write_json(&self, sources: &Sources, out: &mut String) {
+        out.push_str("{\"severity\":");
+        write_string(out, &self.severity.to_string());
+        out.push_str(",\"code\":");
+        write_optional(out, self.code.as_deref());
+        out.push_str(",\"message\":");
+        write_string(out, &self.message);
+        out.push_str(",\"labels\":[");
+        write_label(out, sources, &self.primary, true);
+        for label in &self.secondary {
+            out.push(',');
+            write_label(out, sources, label, false);
+        }
+        out.push_str("],\"notes\":[");
+        for (i, note) in self.notes.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            write_string(out, note);
+        }
+        out.push_str("],\"help\":");
+        write_optional(out, self.help.as_deref());
+        out.push('}');
+    }
+}
+
+impl Diagnostics {
+    /// Renders every diagnostic against the files in `sources`. The text formats
+    /// separate reports with a blank line, and JSON is a single array.
+    pub fn render(&self, sources: &Sources, format: Format) -> String {
+        match format {
+            Format::Colored | Format::Plain => self
+                .iter()
+                .map(|diagnostic| diagnostic.render(sources, format))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Format::Json => {
+                let mut json = String::from("[");
+                for (i, diagnostic) in self.iter().enumerate() {
+                    if i > 0 {
+                        json.push(',');
+                    }
+                    diagnostic.write_json(sources, &mut json);
+                }
+                json.push(']');
+                json
+            }
+        }
+    }
+}
+
+fn write_label(out: &mut String, sources: &Sources, label: &Label, primary: bool) {
+    let (line, column) = sources.location(label.span.file, label.span.start);
+    let (end_line, end_column) = sources.location(label.span.file, label.span.end);
+    out.push_str("{\"file\":");
+    write_string(out, sources.name(label.span.file));
+    write!(
+        out,
+        ",\"primary\":{},\"start\":{},\"end\":{},\"line\":{},\"column\":{},\"end_line\":{},\"end_column\":{},\"message\":",
+        primary, label.span.start, label.span.end, line, column, end_line, end_column
+    )
+    .unwrap();
+    write_optional(out, label.message.as_deref());
+    out.push('}');
+}
+
+fn write_optional(out: &mut String, value: Option<&str>) {
+    match value {
+        Some(value) => write_string(out, value),
+        None => out.push_str("null"),
+    }
+}
+
+/// Writes `value` as a quoted JSON string
+fn write_string(out: &mut String, value: &str) {
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{parse_policy_file, FileId, Span};
+
+    fn sources() -> Sources {
+        let mut sources = Sources::new();
+        sources.add("lib.sentinel", "limit = 10\n");
+        sources.add("main.sentinel", "x = limit +\n");
+        sources
+    }
+
+    fn diagnostic() -> Diagnostic {
+        Diagnostic::error("missing operand", Span::new(FileId(1), 10..11))
+            .with_code("E0001")
+            .with_primary_message("expected an expression after `+`")
+            .with_label(
+                Label::new(Span::new(FileId(0), 0..5)).with_message("`limit` is defined here"),
+            )
+            .with_note("operators need two operands")
+            .with_help("remove the trailing \"+\"")
+    }
+
+    #[test]
+    fn test_render_plain() {
+        let output = diagnostic().render(&sources(), Format::Plain);
+        assert!(
+            output.starts_with("[E0001] Error: missing operand"),
+            "{}",
+            output
+        );
+        assert!(output.contains("main.sentinel:1:11"), "{}", output);
+        assert!(output.contains("lib.sentinel"), "{}", output);
+        assert!(
+            output.contains("expected an expression after `+`"),
+            "{}",
+            output
+        );
+        assert!(output.contains("`limit` is defined here"), "{}", output);
+        assert!(
+            output.contains("Note: operators need two operands"),
+            "{}",
+            output
+        );
+        assert!(
+            output.contains("Help: remove the trailing \"+\""),
+            "{}",
+            output
+        );
+        assert!(!output.contains('\u{1b}'), "{}", output);
+    }
+
+    #[test]
+    fn test_render_colored() {
+        let output = diagnostic().render(&sources(), Format::Colored);
+        assert!(output.contains("╭─[main.sentinel:1:11]"), "{}", output);
+        assert!(output.contains("missing operand"), "{}", output);
+    }
+
+    #[test]
+    fn test_render_json() {
+        assert_eq!(
+            Diagnostics(vec![diagnostic()]).render(&sources(), Format::Json),
+            concat!(
+                r#"[{"severity":"error","code":"E0001","message":"missing operand","labels":["#,
+                r#"{"file":"main.sentinel","primary":true,"start":10,"end":11,"line":1,"column":11,"end_line":1,"end_column":12,"message":"expected an expression after `+`"},"#,
+                r#"{"file":"lib.sentinel","primary":false,"start":0,"end":5,"line":1,"column":1,"end_line":1,"end_column":6,"message":"`limit` is defined here"}],"#,
+                r#""notes":["operators need two operands"],"help":"remove the trailing \"+\""}]"#,
+            )
+        );
+        assert_eq!(
+            Diagnostics::default().render(&sources(), Format::Json),
+            "[]"
+        );
+    }
+
+    #[test]
+    fn test_render_syntax_errors() {
+        let mut sources = sources();
+        let file = sources.add("broken.sentinel", "x = (1 +)\ny = [,]");
+        let diagnostics = parse_policy_file(file, sources.source(file)).unwrap_err();
+        assert_eq!(diagnostics.len(), 2);
+        let json = diagnostics.render(&sources, Format::Json);
+        assert!(json.contains(r#""file":"broken.sentinel""#), "{}", json);
+        assert!(json.contains(r#""line":2,"column":6"#), "{}", json);
+        let text = diagnostics.render(&sources, Format::Plain);
+        assert!(text.contains("broken.sentinel:2:6"), "{}", text);
+    }
+}
@@ -0,0 +1,69 @@
+use crate::parser::FileId;
+
+/// The name and text of every file that diagnostics may point into. Each file is
+/// identified by the `FileId` returned when it is added.
+#[derive(Debug, Default, Clone)]
+pub struct Sources {
+    files: Vec<(String, String)>,
+}
+
+impl Sources {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a file, returning the id to parse it with
+    pub fn add<N: Into<String>, S: Into<String>>(&mut self, name: N, source: S) -> FileId {
+        self.files.push((name.into(), source.into()));
+        FileId(self.files.len() - 1)
+    }
+
+    pub fn name(&self, file: FileId) -> &str {
+        self.files
+            .get(file.0)
+            .map_or("<unknown>", |(name, _)| name.as_str())
+    }
+
+    pub fn source(&self, file: FileId) -> &str {
+        self.files
+            .get(file.0)
+            .map_or("", |(_, source)| source.as_str())
+    }
+
+    pub(crate) fn files(&self) -> impl Iterator<Item = &(String, String)> {
+        self.files.iter()
+    }
+
+    /// The 1-based line and column of a byte offset into a file. Columns count
+    /// characters rather than bytes.
+    pub fn location(&self, file: FileId, offset: usize) -> (usize, usize) {
+        let source = self.source(file);
+        let mut offset = offset.min(source.len());
+        while !source.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        let before = &source[..offset];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        (
+            before.matches('\n').count() + 1,
+            before[line_start..].chars().count() + 1,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_location() {
+        let mut sources = Sources::new();
+        let main = sources.add("main.sentinel", "x = 1\ny = \"é\" +");
+        assert_eq!(main, FileId(0));
+        assert_eq!(sources.location(main, 0), (1, 1));
+        assert_eq!(sources.location(main, 6), (2, 1));
+        assert_eq!(sources.location(main, 14), (2, 8));
+        assert_eq!(sources.location(main, 100), (2, 10));
+        assert_eq!(sources.name(FileId(7)), "<unknown>");
+    }
+}
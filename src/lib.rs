@@ -1,18 +1,17 @@
+//! A parser and evaluator for Sentinel-style policy files. Use [`parse_policy`] to parse
+//! a policy into its [`Policy`] syntax tree, and [`Policy::evaluate`] to run it with the
+//! [`Parameters`] supplied by the host.
+
 #![allow(dead_code)]
 
+mod diagnostic;
 mod parser;
+mod runtime;
 
-pub fn add(left: usize, right: usize) -> usize {
-    left + right
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn it_works() {
-        let result = add(2, 2);
-        assert_eq!(result, 4);
-    }
-}
+pub use diagnostic::{Diagnostic, Diagnostics, Format, Label, Severity, Sources, SYNTAX_ERROR};
+pub use parser::{
+    parse_policy, parse_policy_file, parse_policy_partial, BinaryOperator, Expression, FileId,
+    Identifier, Import, Item, Literal, Param, Policy, QuantifierType, Rule, Span, Spanned,
+    Statement, StringPart, UnaryOperator,
+};
+pub use runtime::{Evaluation, Frame, Function, Parameters, RuntimeError, Scope, Value};
@@ -1,4 +1,4 @@
-use crate::parser::{Parsable, ParsableError};
+use crate::parser::{keyword, op, Parsable, ParsableError, ParserInput};
 use chumsky::prelude::*;
 
 #[derive(Debug, PartialEq, Clone)]
@@ -18,72 +18,86 @@ pub enum BinaryOperator {
     Or,
     Xor,
     Contains,
+    NotContains,
     In,
+    NotIn,
     Matches,
     NotMatches,
     Is,
     IsNot,
+    /// `x else y` evaluates to `y` only when `x` is undefined
+    Else,
 }
 
 impl Parsable for BinaryOperator {
-    fn parser<'src>() -> impl Parser<'src, &'src str, Self, ParsableError<'src>> {
+    fn parser<'src>() -> impl Parser<'src, ParserInput<'src>, Self, ParsableError<'src>> {
         choice((
             Self::additive(),
             Self::multiplicative(),
             Self::comparison(),
             Self::and(),
             Self::or_xor(),
+            Self::else_(),
         ))
     }
 }
 
 impl BinaryOperator {
-    pub(crate) fn multiplicative<'src>() -> impl Parser<'src, &'src str, Self, ParsableError<'src>>
-    {
+    pub(crate) fn multiplicative<'src>(
+    ) -> impl Parser<'src, ParserInput<'src>, Self, ParsableError<'src>> {
         choice((
-            just('*').to(BinaryOperator::Multiply),
-            just('/').to(BinaryOperator::Divide),
-            just('%').to(BinaryOperator::Modulus),
+            op("*").to(BinaryOperator::Multiply),
+            op("/").to(BinaryOperator::Divide),
+            op("%").to(BinaryOperator::Modulus),
         ))
-        .padded()
     }
 
-    pub(crate) fn additive<'src>() -> impl Parser<'src, &'src str, Self, ParsableError<'src>> {
+    pub(crate) fn additive<'src>() -> impl Parser<'src, ParserInput<'src>, Self, ParsableError<'src>>
+    {
         choice((
-            just('+').to(BinaryOperator::Add),
-            just('-').to(BinaryOperator::Subtract),
+            op("+").to(BinaryOperator::Add),
+            op("-").to(BinaryOperator::Subtract),
         ))
-        .padded()
     }
 
-    pub(crate) fn comparison<'src>() -> impl Parser<'src, &'src str, Self, ParsableError<'src>> {
+    pub(crate) fn comparison<'src>(
+    ) -> impl Parser<'src, ParserInput<'src>, Self, ParsableError<'src>> {
         choice((
-            just("==").to(BinaryOperator::Equals),
-            just("!=").to(BinaryOperator::NotEquals),
-            just("<=").to(BinaryOperator::LessThanOrEqual),
-            just('<').to(BinaryOperator::LessThan),
-            just(">=").to(BinaryOperator::GreaterThanOrEqual),
-            just('>').to(BinaryOperator::GreaterThan),
-            just("is not").to(BinaryOperator::IsNot),
-            just("is").to(BinaryOperator::Is),
-            just("matches").to(BinaryOperator::Matches),
-            just("not matches").to(BinaryOperator::NotMatches),
-            just("contains").to(BinaryOperator::Contains),
-            just("in").to(BinaryOperator::In),
+            op("==").to(BinaryOperator::Equals),
+            op("!=").to(BinaryOperator::NotEquals),
+            op("<=").to(BinaryOperator::LessThanOrEqual),
+            op("<").to(BinaryOperator::LessThan),
+            op(">=").to(BinaryOperator::GreaterThanOrEqual),
+            op(">").to(BinaryOperator::GreaterThan),
+            keyword("is").then(keyword("not")).to(BinaryOperator::IsNot),
+            keyword("is").to(BinaryOperator::Is),
+            keyword("not")
+                .then(keyword("matches"))
+                .to(BinaryOperator::NotMatches),
+            keyword("not")
+                .then(keyword("contains"))
+                .to(BinaryOperator::NotContains),
+            keyword("not").then(keyword("in")).to(BinaryOperator::NotIn),
+            keyword("matches").to(BinaryOperator::Matches),
+            keyword("contains").to(BinaryOperator::Contains),
+            keyword("in").to(BinaryOperator::In),
         ))
-        .padded()
     }
 
-    pub(crate) fn and<'src>() -> impl Parser<'src, &'src str, Self, ParsableError<'src>> {
-        just("and").to(BinaryOperator::And).padded()
+    pub(crate) fn and<'src>() -> impl Parser<'src, ParserInput<'src>, Self, ParsableError<'src>> {
+        keyword("and").to(BinaryOperator::And)
     }
 
-    pub(crate) fn or_xor<'src>() -> impl Parser<'src, &'src str, Self, ParsableError<'src>> {
+    pub(crate) fn or_xor<'src>() -> impl Parser<'src, ParserInput<'src>, Self, ParsableError<'src>>
+    {
         choice((
-            just("or").to(BinaryOperator::Or),
-            just("xor").to(BinaryOperator::Xor),
+            keyword("or").to(BinaryOperator::Or),
+            keyword("xor").to(BinaryOperator::Xor),
         ))
-        .padded()
+    }
+
+    pub(crate) fn else_<'src>() -> impl Parser<'src, ParserInput<'src>, Self, ParsableError<'src>> {
+        keyword("else").to(BinaryOperator::Else)
     }
 }
 
@@ -109,10 +123,23 @@ mod tests {
         test_parser("xor", BinaryOperator::Xor);
         test_parser("contains", BinaryOperator::Contains);
         test_parser("in", BinaryOperator::In);
+        test_parser("not contains", BinaryOperator::NotContains);
+        test_parser("not in", BinaryOperator::NotIn);
         test_parser("not matches", BinaryOperator::NotMatches);
         test_parser("matches", BinaryOperator::Matches);
         test_parser("is not", BinaryOperator::IsNot);
         test_parser("is", BinaryOperator::Is);
+        test_parser("else", BinaryOperator::Else);
+    }
+
+    #[test]
+    fn test_keyword_boundaries() {
+        test_parser::<BinaryOperator, &str>("index", "found index expected");
+        test_parser::<BinaryOperator, &str>("issuer", "found issuer expected");
+        test_parser::<BinaryOperator, &str>("origin", "found origin expected");
+        test_parser::<BinaryOperator, &str>("android", "found android expected");
+        test_parser("not  matches", BinaryOperator::NotMatches);
+        test_parser("is\nnot", BinaryOperator::IsNot);
     }
 
     impl From<BinaryOperator> for Expect<BinaryOperator> {
@@ -1,17 +1,107 @@
+use crate::parser::LexError;
 use chumsky::prelude::*;
 
-// fn comment<'src>() -> impl Parser<'src, &'src str, (), extra::Err<Rich<'src, char>>> {
-//     let single_line = just("//")
-//         .or(just("#"))
-//         .then(take_until(text::newline()).ignored());
-//
-//     let multi_line = just("/*").then(take_until(just("*/"))).ignored();
-//
-//     single_line.or(multi_line).ignored()
-// }
+/// Parses any amount of whitespace and comments. Comments are treated as trivia and
+/// are accepted anywhere whitespace is.
+///
+/// ```text
+/// // single line comment
+/// #  single line comment
+/// /* multi
+///    line comment */
+/// ```
+pub(crate) fn trivia<'src>() -> impl Parser<'src, &'src str, (), LexError<'src>> + Clone {
+    let single_line = just("//")
+        .or(just("#"))
+        .then(any().and_is(text::newline().not()).repeated())
+        .ignored();
+
+    // An unterminated block comment swallows the rest of the input and is reported
+    // as an error spanning from the opening `/*`.
+    let multi_line = just("/*")
+        .then(any().and_is(just("*/").not()).repeated())
+        .then(just("*/").or_not())
+        .validate(|(_, close), e, emitter| {
+            if close.is_none() {
+                emitter.emit(Rich::custom(e.span(), "unterminated block comment"))
+            }
+        })
+        .ignored();
+
+    choice((
+        text::whitespace().at_least(1).ignored(),
+        single_line,
+        multi_line,
+    ))
+    .repeated()
+}
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use crate::parser::{
+        ident, int, test_parser, BinaryOperator, Expression, Identifier, Statement,
+    };
+
+    #[test]
+    fn test_comment() {
+        for input in [
+            "",
+            "   ",
+            "// comment",
+            "# comment\n",
+            "/* comment */",
+            "/* multi\n line */ // and more\n # and more",
+        ] {
+            let result = trivia().then_ignore(end()).parse(input);
+            assert!(!result.has_errors(), "input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_unterminated_block_comment() {
+        let errors = trivia().parse("  /* never closed").into_errors();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].reason().to_string(), "unterminated block comment");
+        assert_eq!(errors[0].span().into_range(), 2..17);
+    }
+
+    #[test]
+    fn test_comments_in_expressions() {
+        test_parser(
+            "a /* lhs */ + // rhs follows\n b",
+            Expression::binary_expr(ident("a"), BinaryOperator::Add, ident("b")),
+        );
+        test_parser(
+            "[\n  1, // one\n  # two\n  2, /* trailing */\n]",
+            Expression::list(vec![int(1), int(2)]),
+        );
+        test_parser(
+            "{\n  // key\n  a: 1, /* value */\n}",
+            Expression::map(vec![(ident("a"), int(1))]),
+        );
+        test_parser(
+            "f(/* first */ a, // second\n b)",
+            Expression::call(Identifier::new("f"), vec![ident("a"), ident("b")]),
+        );
+    }
+
     #[test]
-    fn test_comment() {}
+    fn test_comments_between_statements() {
+        test_parser(
+            "{\n  // set x\n  x = 1 # inline\n  /* set\n  y */ y = 2\n}",
+            Statement::block(vec![
+                Statement::assignment(ident("x"), int(1)),
+                Statement::assignment(ident("y"), int(2)),
+            ]),
+        );
+        test_parser(
+            "if x { // comment\n  return // nothing\n}",
+            Statement::If {
+                condition: ident("x").into(),
+                then_branch: Box::new(Statement::block(vec![Statement::Return(None)]).into()),
+                else_branch: None,
+            },
+        );
+    }
 }
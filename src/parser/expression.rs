@@ -1,8 +1,11 @@
 use crate::parser::{
-    BinaryOperator, Identifier, Literal, Parsable, ParsableError, QuantifierType, UnaryOperator,
+    balanced, ctrl, keyword, open_brace, select_token, BinaryOperator, Identifier, Literal,
+    Parsable, ParsableError, ParserInput, QuantifierType, Span, Spanned, Statement, Token,
+    UnaryOperator,
 };
-use chumsky::pratt::{infix, Associativity};
+use chumsky::pratt::{infix, postfix, prefix, Associativity};
 use chumsky::prelude::*;
+use std::collections::HashSet;
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum Expression {
@@ -10,163 +13,516 @@ pub enum Expression {
     Identifier(Identifier),
     UnaryExpr {
         op: UnaryOperator,
-        expr: Box<Expression>,
+        expr: Box<Spanned<Expression>>,
     },
     BinaryExpr {
-        left: Box<Expression>,
+        left: Box<Spanned<Expression>>,
         op: BinaryOperator,
-        right: Box<Expression>,
+        right: Box<Spanned<Expression>>,
     },
     Call {
-        func: Identifier,
-        args: Vec<Expression>,
+        func: Box<Spanned<Expression>>,
+        args: Vec<Spanned<Expression>>,
     },
     Index {
-        collection: Box<Expression>,
-        index: Box<Expression>,
+        collection: Box<Spanned<Expression>>,
+        index: Box<Spanned<Expression>>,
     },
     Slice {
-        collection: Box<Expression>,
-        start: Option<Box<Expression>>,
-        end: Option<Box<Expression>>,
+        collection: Box<Spanned<Expression>>,
+        start: Option<Box<Spanned<Expression>>>,
+        end: Option<Box<Spanned<Expression>>>,
     },
     Select {
-        object: Box<Expression>,
-        field: Identifier,
+        object: Box<Spanned<Expression>>,
+        field: Spanned<Identifier>,
     },
-    List(Vec<Expression>),
-    Map(Vec<(Expression, Expression)>),
+    List(Vec<Spanned<Expression>>),
+    Map(Vec<(Spanned<Expression>, Spanned<Expression>)>),
     Rule {
-        when: Option<Box<Expression>>,
-        body: Box<Expression>,
+        when: Option<Box<Spanned<Expression>>>,
+        body: Box<Spanned<Expression>>,
     },
     Quantifier {
         quant: QuantifierType,
-        collection: Box<Expression>,
-        key: Option<Identifier>,
-        value: Identifier,
-        body: Box<Expression>,
+        collection: Box<Spanned<Expression>>,
+        key: Option<Spanned<Identifier>>,
+        value: Spanned<Identifier>,
+        body: Box<Spanned<Expression>>,
     },
+    Function {
+        params: Vec<Spanned<Identifier>>,
+        body: Box<Spanned<Statement>>,
+    },
+    /// A string with embedded expressions, e.g. `"instance ${r.address}"`
+    Interpolation(Vec<StringPart>),
+    /// Stands in for source that failed to parse, so the rest of the tree survives
+    Error,
+}
+
+/// A piece of an interpolated string
+#[derive(Debug, PartialEq, Clone)]
+pub enum StringPart {
+    Text(String),
+    /// The expression inside `${...}`
+    Expression(Spanned<Expression>),
 }
 
 impl Expression {
-    pub fn literal<'src>() -> impl Parser<'src, &'src str, Self, ParsableError<'src>> {
+    pub(crate) fn literal<'src>() -> impl Parser<'src, ParserInput<'src>, Self, ParsableError<'src>>
+    {
         Literal::parser().map(Expression::Literal)
     }
 
-    pub fn identifier<'src>() -> impl Parser<'src, &'src str, Self, ParsableError<'src>> {
+    pub(crate) fn identifier<'src>(
+    ) -> impl Parser<'src, ParserInput<'src>, Self, ParsableError<'src>> {
         Identifier::parser().map(Expression::Identifier)
     }
 
-    pub fn function<'src>(
-        args: impl Parser<'src, &'src str, Self, ParsableError<'src>>,
-    ) -> impl Parser<'src, &'src str, Self, ParsableError<'src>> {
-        Identifier::parser()
-            .then(
-                args.separated_by(just(',').padded())
-                    .collect::<Vec<_>>()
-                    .delimited_by(just('(').padded(), just(')').padded()),
-            )
-            .map(|(func, args)| Expression::Call { func, args })
+    /// Parses a single postfix operation (call, index, slice or selector) that can
+    /// be chained onto any primary expression.
+    fn postfix<'src>(
+        expr: impl Parser<'src, ParserInput<'src>, Spanned<Self>, ParsableError<'src>> + Clone,
+    ) -> impl Parser<'src, ParserInput<'src>, Spanned<Postfix>, ParsableError<'src>> {
+        let call = expr
+            .clone()
+            .separated_by(ctrl(','))
+            .allow_trailing()
+            .collect::<Vec<_>>()
+            .delimited_by(ctrl('('), ctrl(')'))
+            .map(Postfix::Call)
+            .recover_with(via_parser(balanced('(', ')', |span| {
+                Postfix::Call(vec![Spanned::new(Expression::Error, span)])
+            })));
+
+        // Slices are tried first so that `[a]` falls back to a plain index
+        let slice = expr
+            .clone()
+            .or_not()
+            .then_ignore(ctrl(':'))
+            .then(expr.clone().or_not())
+            .map(|(start, end)| Postfix::Slice(start, end));
+        let index = choice((slice, expr.map(Postfix::Index)))
+            .delimited_by(ctrl('['), ctrl(']'))
+            .recover_with(via_parser(balanced('[', ']', |span| {
+                Postfix::Index(Spanned::new(Expression::Error, span))
+            })));
+
+        let select = ctrl('.')
+            .ignore_then(Identifier::field().map_with(|field, e| Spanned::new(field, e.span())))
+            .map(Postfix::Select);
+
+        choice((call, index, select)).map_with(|op, e| Spanned::new(op, e.span()))
+    }
+}
+
+/// A postfix operation waiting to be folded onto the expression to its left
+enum Postfix {
+    Call(Vec<Spanned<Expression>>),
+    Index(Spanned<Expression>),
+    Slice(Option<Spanned<Expression>>, Option<Spanned<Expression>>),
+    Select(Spanned<Identifier>),
+}
+
+impl Postfix {
+    fn fold(self, lhs: Spanned<Expression>) -> Expression {
+        let lhs = Box::new(lhs);
+        match self {
+            Postfix::Call(args) => Expression::Call { func: lhs, args },
+            Postfix::Index(index) => Expression::Index {
+                collection: lhs,
+                index: Box::new(index),
+            },
+            Postfix::Slice(start, end) => Expression::Slice {
+                collection: lhs,
+                start: start.map(Box::new),
+                end: end.map(Box::new),
+            },
+            Postfix::Select(field) => Expression::Select { object: lhs, field },
+        }
     }
 }
 
+/// Joins two operands with a binary operator, spanning both of them
+fn binary(
+    left: Spanned<Expression>,
+    op: BinaryOperator,
+    right: Spanned<Expression>,
+) -> Spanned<Expression> {
+    let span = left.span.union(right.span);
+    Spanned::new(
+        Expression::BinaryExpr {
+            left: Box::new(left),
+            op,
+            right: Box::new(right),
+        },
+        span,
+    )
+}
+
+/// Applies a unary operator whose own span is `op_span`
+fn unary((op, op_span): (UnaryOperator, Span), expr: Spanned<Expression>) -> Spanned<Expression> {
+    let span = op_span.union(expr.span);
+    Spanned::new(
+        Expression::UnaryExpr {
+            op,
+            expr: Box::new(expr),
+        },
+        span,
+    )
+}
+
 impl Parsable for Expression {
-    fn parser<'src>() -> impl Parser<'src, &'src str, Self, ParsableError<'src>> {
+    fn parser<'src>() -> impl Parser<'src, ParserInput<'src>, Self, ParsableError<'src>> {
+        Spanned::<Expression>::parser().map(|expr| expr.node)
+    }
+}
+
+impl Parsable for Spanned<Expression> {
+    fn parser<'src>() -> impl Parser<'src, ParserInput<'src>, Self, ParsableError<'src>> {
         recursive(|expr| {
             // Define the literal and identifier parsers
-            let literal = Literal::parser().map(Expression::Literal).boxed();
-            let identifier = Identifier::parser().map(Expression::Identifier).boxed();
-            let unary = UnaryOperator::parser()
+            let literal = Expression::literal().boxed();
+            let identifier = Expression::identifier().boxed();
+            // ( <expr> )
+            let parenthesized = expr
+                .clone()
+                .delimited_by(ctrl('('), ctrl(')'))
+                .map(|expr: Spanned<Expression>| expr.node)
+                .recover_with(via_parser(balanced('(', ')', |_| Expression::Error)))
+                .boxed();
+
+            // [a, b, c]
+            let list = expr
+                .clone()
+                .separated_by(ctrl(','))
+                .allow_trailing()
+                .collect::<Vec<_>>()
+                .delimited_by(ctrl('['), ctrl(']'))
+                .map(Expression::List)
+                .recover_with(via_parser(balanced('[', ']', |_| Expression::Error)))
+                .boxed();
+
+            // {key: value, ...}
+            let map = expr
+                .clone()
+                .then_ignore(ctrl(':'))
+                .then(expr.clone())
+                .separated_by(ctrl(','))
+                .allow_trailing()
+                .collect::<Vec<_>>()
+                .delimited_by(ctrl('{'), ctrl('}'))
+                .map(Expression::Map)
+                .recover_with(via_parser(balanced('{', '}', |_| Expression::Error)))
+                .boxed();
+
+            // "text ${<expr>} text"
+            let interpolation = choice((
+                select_token! { Token::StringText(text) => StringPart::Text(text) },
+                just(Token::InterpolationStart)
+                    .then(just(Token::InterpolationEnd))
+                    .validate(|_, e, emitter| {
+                        emitter.emit(Rich::custom(e.span(), "empty interpolation"));
+                        StringPart::Expression(Spanned::new(Expression::Error, e.span()))
+                    }),
+                expr.clone()
+                    .delimited_by(
+                        just(Token::InterpolationStart),
+                        just(Token::InterpolationEnd),
+                    )
+                    .map(StringPart::Expression),
+            ))
+            .repeated()
+            .collect::<Vec<_>>()
+            .delimited_by(just(Token::StringStart), just(Token::StringEnd))
+            .map(Expression::Interpolation)
+            .boxed();
+
+            // func(a, b) { <statements> }
+            let function = keyword("func")
+                .ignore_then(
+                    Identifier::name()
+                        .separated_by(ctrl(','))
+                        .allow_trailing()
+                        .collect::<Vec<_>>()
+                        .delimited_by(ctrl('('), ctrl(')')),
+                )
+                .then(Statement::block_parser(Statement::parser_with(
+                    expr.clone(),
+                )))
+                .validate(|(params, body), _, emitter| {
+                    let mut seen = HashSet::new();
+                    for param in &params {
+                        if !seen.insert(&param.0) {
+                            emitter.emit(Rich::custom(
+                                param.span,
+                                format!("duplicate parameter `{}`", param.0),
+                            ));
+                        }
+                    }
+                    if let Some(span) = body.loop_control() {
+                        emitter.emit(Rich::custom(
+                            span,
+                            "`break` and `continue` are only allowed inside a `for` loop",
+                        ));
+                    }
+                    Expression::Function {
+                        params,
+                        body: Box::new(body),
+                    }
+                })
+                .boxed();
+
+            // { <expr> }, as in the body of a rule or quantifier
+            let body = expr
+                .clone()
+                .delimited_by(open_brace(), ctrl('}'))
+                .recover_with(via_parser(balanced('{', '}', |span| {
+                    Spanned::new(Expression::Error, span)
+                })))
+                .boxed();
+
+            // rule [when <cond>] { <expr> }
+            let rule = keyword("rule")
+                .ignore_then(keyword("when").ignore_then(expr.clone()).or_not())
+                .then(body.clone())
+                .map(|(when, body)| Expression::Rule {
+                    when: when.map(Box::new),
+                    body: Box::new(body),
+                })
+                .boxed();
+
+            // all|any|filter|map <collection> as [<key>,] <value> { <expr> }
+            let quantifier = QuantifierType::parser()
                 .then(expr.clone())
-                .map(|(op, expr)| Expression::UnaryExpr {
-                    op,
-                    expr: Box::new(expr),
-                });
-            let function = Self::function(expr);
+                .then_ignore(keyword("as"))
+                .then(Identifier::name().then_ignore(ctrl(',')).or_not())
+                .then(Identifier::name())
+                .then(body)
+                .map(
+                    |((((quant, collection), key), value), body)| Expression::Quantifier {
+                        quant,
+                        collection: Box::new(collection),
+                        key,
+                        value,
+                        body: Box::new(body),
+                    },
+                )
+                .boxed();
 
             // Define the primary expression parser
-            let primary = choice((function, literal, identifier, unary)).boxed();
+            let primary = choice((
+                function,
+                rule,
+                quantifier,
+                literal,
+                interpolation,
+                identifier,
+                list,
+                map,
+                parenthesized,
+                Identifier::reserved_operand().to(Expression::Error),
+            ))
+            .labelled("an expression")
+            .map_with(|expr, e| Spanned::new(expr, e.span()))
+            .foldl(Expression::postfix(expr).repeated(), |lhs, op| {
+                let span = lhs.span.union(op.span);
+                Spanned::new(op.node.fold(lhs), span)
+            })
+            .boxed();
 
-            // Define the Pratt parser for binary expressions
+            // Define the Pratt parser for prefix and binary expressions. Prefix operators
+            // bind tighter than any binary operator, so `-x * y` is `(-x) * y`. The minus
+            // in `-9223372036854775808` is part of the literal rather than an operator.
             primary.clone().pratt((
+                prefix(
+                    7,
+                    Literal::min_integer()
+                        .not()
+                        .ignore_then(UnaryOperator::prefix())
+                        .map_with(|op, e| (op, e.span()))
+                        .boxed(),
+                    unary,
+                ),
                 infix(
-                    Associativity::Left(5),
+                    Associativity::Left(6),
                     BinaryOperator::multiplicative().boxed(),
-                    |left, op, right| Expression::BinaryExpr {
-                        left: Box::new(left),
-                        op,
-                        right: Box::new(right),
-                    },
+                    binary,
                 ),
                 infix(
-                    Associativity::Left(4),
+                    Associativity::Left(5),
                     BinaryOperator::additive().boxed(),
-                    |left, op, right| Expression::BinaryExpr {
-                        left: Box::new(left),
-                        op,
-                        right: Box::new(right),
-                    },
+                    binary,
+                ),
+                // `is empty` and `is defined` are tried before the binary `is` operator, so
+                // `tags is not empty` is a postfix test rather than a comparison with `empty`
+                postfix(
+                    4,
+                    UnaryOperator::postfix()
+                        .map_with(|op, e| (op, e.span()))
+                        .boxed(),
+                    |expr, op| unary(op, expr),
                 ),
                 infix(
                     Associativity::Left(3),
                     BinaryOperator::comparison().boxed(),
-                    |left, op, right| Expression::BinaryExpr {
-                        left: Box::new(left),
-                        op,
-                        right: Box::new(right),
-                    },
+                    binary,
                 ),
                 infix(
                     Associativity::Left(2),
                     BinaryOperator::and().boxed(),
-                    |left, op, right| Expression::BinaryExpr {
-                        left: Box::new(left),
-                        op,
-                        right: Box::new(right),
-                    },
+                    binary,
                 ),
                 infix(
                     Associativity::Left(1),
                     BinaryOperator::or_xor().boxed(),
-                    |left, op, right| Expression::BinaryExpr {
-                        left: Box::new(left),
-                        op,
-                        right: Box::new(right),
-                    },
+                    binary,
+                ),
+                // `else` binds loosest of all, so `a.b or c else false` falls back on the
+                // result of the whole expression
+                infix(
+                    Associativity::Left(0),
+                    BinaryOperator::else_().boxed(),
+                    binary,
                 ),
             ))
         })
     }
 }
 
+impl From<Identifier> for Expression {
+    fn from(value: Identifier) -> Self {
+        Expression::Identifier(value)
+    }
+}
+
+impl Expression {
+    /// Whether a value can be assigned to this expression, which must be a variable or
+    /// an index or field of one
+    pub fn is_assignable(&self) -> bool {
+        match self {
+            Expression::Identifier(_) => true,
+            Expression::Index { collection, .. } => collection.is_assignable(),
+            Expression::Select { object, .. } => object.is_assignable(),
+            _ => false,
+        }
+    }
+}
+
+// The constructors below build nodes by hand, so any operand that is not already
+// spanned is given an empty span.
 impl Expression {
     /// Create a new unary expression, boxing the expression
-    pub fn unary_expr(op: UnaryOperator, expr: Self) -> Self {
+    pub fn unary_expr(op: UnaryOperator, expr: impl Into<Spanned<Self>>) -> Self {
         Expression::UnaryExpr {
             op,
-            expr: Box::new(expr),
+            expr: Box::new(expr.into()),
         }
     }
 
     /// Create a new binary expression, boxing the left and right expressions
-    pub fn binary_expr(lhs: Self, op: BinaryOperator, rhs: Self) -> Self {
+    pub fn binary_expr(
+        lhs: impl Into<Spanned<Self>>,
+        op: BinaryOperator,
+        rhs: impl Into<Spanned<Self>>,
+    ) -> Self {
         Expression::BinaryExpr {
-            left: Box::new(lhs),
+            left: Box::new(lhs.into()),
             op,
-            right: Box::new(rhs),
+            right: Box::new(rhs.into()),
         }
     }
 
-    pub fn call(func: Identifier, args: Vec<Expression>) -> Self {
-        Expression::Call { func, args }
+    /// Create a new call expression, boxing the callee
+    pub fn call<F: Into<Expression>>(func: F, args: Vec<Expression>) -> Self {
+        Expression::Call {
+            func: Box::new(func.into().into()),
+            args: args.into_iter().map(Spanned::from).collect(),
+        }
+    }
+
+    /// Create a new index expression, boxing the collection and index
+    pub fn index(collection: impl Into<Spanned<Self>>, index: impl Into<Spanned<Self>>) -> Self {
+        Expression::Index {
+            collection: Box::new(collection.into()),
+            index: Box::new(index.into()),
+        }
+    }
+
+    /// Create a new slice expression, boxing the collection and bounds
+    pub fn slice(
+        collection: impl Into<Spanned<Self>>,
+        start: Option<Self>,
+        end: Option<Self>,
+    ) -> Self {
+        Expression::Slice {
+            collection: Box::new(collection.into()),
+            start: start.map(|start| Box::new(start.into())),
+            end: end.map(|end| Box::new(end.into())),
+        }
+    }
+
+    /// Create a new selector expression, boxing the object
+    pub fn select(object: impl Into<Spanned<Self>>, field: Identifier) -> Self {
+        Expression::Select {
+            object: Box::new(object.into()),
+            field: field.into(),
+        }
+    }
+
+    /// Create a new list expression
+    pub fn list(items: Vec<Expression>) -> Self {
+        Expression::List(items.into_iter().map(Spanned::from).collect())
+    }
+
+    /// Create a new map expression from its key/value pairs
+    pub fn map(entries: Vec<(Expression, Expression)>) -> Self {
+        Expression::Map(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key.into(), value.into()))
+                .collect(),
+        )
+    }
+
+    /// Create a new quantifier expression, boxing the collection and body
+    pub fn quantifier(
+        quant: QuantifierType,
+        collection: impl Into<Spanned<Self>>,
+        key: Option<Identifier>,
+        value: Identifier,
+        body: impl Into<Spanned<Self>>,
+    ) -> Self {
+        Expression::Quantifier {
+            quant,
+            collection: Box::new(collection.into()),
+            key: key.map(Spanned::from),
+            value: value.into(),
+            body: Box::new(body.into()),
+        }
+    }
+
+    /// Create a new function literal, boxing the body
+    pub fn function(params: Vec<Identifier>, body: impl Into<Spanned<Statement>>) -> Self {
+        Expression::Function {
+            params: params.into_iter().map(Spanned::from).collect(),
+            body: Box::new(body.into()),
+        }
+    }
+
+    /// Create a new rule expression with an optional `when` guard
+    pub fn rule(when: Option<Self>, body: impl Into<Spanned<Self>>) -> Self {
+        Expression::Rule {
+            when: when.map(|when| Box::new(when.into())),
+            body: Box::new(body.into()),
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::parser::{test_parser, Expect};
+    use crate::parser::{ident, int, parse, test_parser, test_recovery, Expect, FileId};
 
     #[test]
     fn test_parse_literal() {
@@ -178,6 +534,61 @@ mod tests {
         test_parser("foobar", Expression::Identifier(Identifier::new("foobar")));
     }
 
+    #[test]
+    fn test_min_integer() {
+        test_parser(
+            "-9223372036854775808",
+            Expression::Literal(Literal::Integer(i64::MIN)),
+        );
+        test_parser(
+            "-9223372036854775807",
+            Expression::unary_expr(
+                UnaryOperator::Minus,
+                Expression::Literal(Literal::Integer(i64::MAX)),
+            ),
+        );
+        test_parser(
+            "1 - -9223372036854775808",
+            Expression::binary_expr(
+                Expression::Literal(Literal::Integer(1)),
+                BinaryOperator::Subtract,
+                Expression::Literal(Literal::Integer(i64::MIN)),
+            ),
+        );
+        test_parser::<Expression, &str>("1 - 9223372036854775808", "integer literal is too large");
+    }
+
+    #[test]
+    fn test_reserved_operands() {
+        test_recovery(
+            "default",
+            Expression::Error,
+            &["`default` is a reserved keyword and cannot be used as a name"],
+        );
+        test_recovery(
+            "f(in)",
+            Expression::call(Identifier::new("f"), vec![Expression::Error]),
+            &["`in` is a reserved keyword and cannot be used as a name"],
+        );
+        test_recovery(
+            "a + case",
+            Expression::binary_expr(
+                Expression::Identifier(Identifier::new("a")),
+                BinaryOperator::Add,
+                Expression::Error,
+            ),
+            &["`case` is a reserved keyword"],
+        );
+        test_recovery(
+            "[when, x]",
+            Expression::list(vec![
+                Expression::Error,
+                Expression::Identifier(Identifier::new("x")),
+            ]),
+            &["`when` is a reserved keyword"],
+        );
+    }
+
     #[test]
     fn test_parse_unary_expression() {
         test_parser(
@@ -189,6 +600,259 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_prefix_precedence() {
+        test_parser(
+            "!a and b",
+            Expression::binary_expr(
+                Expression::unary_expr(
+                    UnaryOperator::Not,
+                    Expression::Identifier(Identifier::new("a")),
+                ),
+                BinaryOperator::And,
+                Expression::Identifier(Identifier::new("b")),
+            ),
+        );
+        test_parser(
+            "-x * y",
+            Expression::binary_expr(
+                Expression::unary_expr(
+                    UnaryOperator::Minus,
+                    Expression::Identifier(Identifier::new("x")),
+                ),
+                BinaryOperator::Multiply,
+                Expression::Identifier(Identifier::new("y")),
+            ),
+        );
+        test_parser(
+            "2 * -3 + +4",
+            Expression::binary_expr(
+                Expression::binary_expr(
+                    Expression::Literal(Literal::Integer(2)),
+                    BinaryOperator::Multiply,
+                    Expression::unary_expr(
+                        UnaryOperator::Minus,
+                        Expression::Literal(Literal::Integer(3)),
+                    ),
+                ),
+                BinaryOperator::Add,
+                Expression::unary_expr(
+                    UnaryOperator::Plus,
+                    Expression::Literal(Literal::Integer(4)),
+                ),
+            ),
+        );
+        test_parser(
+            "- -x.y[0]",
+            Expression::unary_expr(
+                UnaryOperator::Minus,
+                Expression::unary_expr(
+                    UnaryOperator::Minus,
+                    Expression::index(
+                        Expression::select(
+                            Expression::Identifier(Identifier::new("x")),
+                            Identifier::new("y"),
+                        ),
+                        Expression::Literal(Literal::Integer(0)),
+                    ),
+                ),
+            ),
+        );
+        test_parser(
+            "!a == b or c",
+            Expression::binary_expr(
+                Expression::binary_expr(
+                    Expression::unary_expr(
+                        UnaryOperator::Not,
+                        Expression::Identifier(Identifier::new("a")),
+                    ),
+                    BinaryOperator::Equals,
+                    Expression::Identifier(Identifier::new("b")),
+                ),
+                BinaryOperator::Or,
+                Expression::Identifier(Identifier::new("c")),
+            ),
+        );
+    }
+
+    #[test]
+    fn test_postfix_unary_operators() {
+        test_parser(
+            "tags is not empty",
+            Expression::unary_expr(
+                UnaryOperator::IsNotEmpty,
+                Expression::Identifier(Identifier::new("tags")),
+            ),
+        );
+        test_parser(
+            "r.tags is empty and r.name is defined",
+            Expression::binary_expr(
+                Expression::unary_expr(
+                    UnaryOperator::IsEmpty,
+                    Expression::select(
+                        Expression::Identifier(Identifier::new("r")),
+                        Identifier::new("tags"),
+                    ),
+                ),
+                BinaryOperator::And,
+                Expression::unary_expr(
+                    UnaryOperator::IsDefined,
+                    Expression::select(
+                        Expression::Identifier(Identifier::new("r")),
+                        Identifier::new("name"),
+                    ),
+                ),
+            ),
+        );
+        test_parser(
+            "a + b is not defined",
+            Expression::unary_expr(
+                UnaryOperator::IsNotDefined,
+                Expression::binary_expr(
+                    Expression::Identifier(Identifier::new("a")),
+                    BinaryOperator::Add,
+                    Expression::Identifier(Identifier::new("b")),
+                ),
+            ),
+        );
+        test_parser(
+            "x is\n  not /* really */ empty",
+            Expression::unary_expr(
+                UnaryOperator::IsNotEmpty,
+                Expression::Identifier(Identifier::new("x")),
+            ),
+        );
+    }
+
+    #[test]
+    fn test_postfix_unary_and_binary_is() {
+        test_parser(
+            "a is not b",
+            Expression::binary_expr(
+                Expression::Identifier(Identifier::new("a")),
+                BinaryOperator::IsNot,
+                Expression::Identifier(Identifier::new("b")),
+            ),
+        );
+        test_parser(
+            "a is emptyish",
+            Expression::binary_expr(
+                Expression::Identifier(Identifier::new("a")),
+                BinaryOperator::Is,
+                Expression::Identifier(Identifier::new("emptyish")),
+            ),
+        );
+        test_parser(
+            "a is b is defined",
+            Expression::binary_expr(
+                Expression::Identifier(Identifier::new("a")),
+                BinaryOperator::Is,
+                Expression::unary_expr(
+                    UnaryOperator::IsDefined,
+                    Expression::Identifier(Identifier::new("b")),
+                ),
+            ),
+        );
+    }
+
+    #[test]
+    fn test_parentheses() {
+        test_parser(
+            "(1 + 2) * 3",
+            Expression::binary_expr(
+                Expression::binary_expr(
+                    Expression::Literal(Literal::Integer(1)),
+                    BinaryOperator::Add,
+                    Expression::Literal(Literal::Integer(2)),
+                ),
+                BinaryOperator::Multiply,
+                Expression::Literal(Literal::Integer(3)),
+            ),
+        );
+        test_parser(
+            "!( a and b )",
+            Expression::unary_expr(
+                UnaryOperator::Not,
+                Expression::binary_expr(
+                    Expression::Identifier(Identifier::new("a")),
+                    BinaryOperator::And,
+                    Expression::Identifier(Identifier::new("b")),
+                ),
+            ),
+        );
+        test_parser(
+            "-(x * y)",
+            Expression::unary_expr(
+                UnaryOperator::Minus,
+                Expression::binary_expr(
+                    Expression::Identifier(Identifier::new("x")),
+                    BinaryOperator::Multiply,
+                    Expression::Identifier(Identifier::new("y")),
+                ),
+            ),
+        );
+        test_parser(
+            "(\n  a or // either\n  b\n).c",
+            Expression::select(
+                Expression::binary_expr(
+                    Expression::Identifier(Identifier::new("a")),
+                    BinaryOperator::Or,
+                    Expression::Identifier(Identifier::new("b")),
+                ),
+                Identifier::new("c"),
+            ),
+        );
+        test_parser::<Expression, &str>("(1 + 2", "found end of input");
+        test_parser::<Expression, &str>("(1 + 2 ]", "found ] expected");
+        test_parser::<Expression, &str>("(1 + 2 ]", "or ')'");
+    }
+
+    #[test]
+    fn test_keyword_boundaries() {
+        test_parser(
+            "index in issuers",
+            Expression::binary_expr(
+                Expression::Identifier(Identifier::new("index")),
+                BinaryOperator::In,
+                Expression::Identifier(Identifier::new("issuers")),
+            ),
+        );
+        test_parser(
+            "origin or android",
+            Expression::binary_expr(
+                Expression::Identifier(Identifier::new("origin")),
+                BinaryOperator::Or,
+                Expression::Identifier(Identifier::new("android")),
+            ),
+        );
+        test_parser(
+            "inputs contains nullable",
+            Expression::binary_expr(
+                Expression::Identifier(Identifier::new("inputs")),
+                BinaryOperator::Contains,
+                Expression::Identifier(Identifier::new("nullable")),
+            ),
+        );
+        test_parser(
+            "a is not_set",
+            Expression::binary_expr(
+                Expression::Identifier(Identifier::new("a")),
+                BinaryOperator::Is,
+                Expression::Identifier(Identifier::new("not_set")),
+            ),
+        );
+        test_parser(
+            "r.default.map",
+            Expression::select(
+                Expression::select(
+                    Expression::Identifier(Identifier::new("r")),
+                    Identifier::new("default"),
+                ),
+                Identifier::new("map"),
+            ),
+        );
+    }
+
     #[test]
     fn test_addition() {
         test_parser(
@@ -266,8 +930,95 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_negated_operators() {
+        test_parser(
+            "a not in b and not c",
+            Expression::binary_expr(
+                Expression::binary_expr(
+                    Expression::Identifier(Identifier::new("a")),
+                    BinaryOperator::NotIn,
+                    Expression::Identifier(Identifier::new("b")),
+                ),
+                BinaryOperator::And,
+                Expression::unary_expr(
+                    UnaryOperator::Not,
+                    Expression::Identifier(Identifier::new("c")),
+                ),
+            ),
+        );
+        test_parser(
+            "not a == b",
+            Expression::binary_expr(
+                Expression::unary_expr(
+                    UnaryOperator::Not,
+                    Expression::Identifier(Identifier::new("a")),
+                ),
+                BinaryOperator::Equals,
+                Expression::Identifier(Identifier::new("b")),
+            ),
+        );
+        test_parser(
+            "tags not contains \"x\" or notes",
+            Expression::binary_expr(
+                Expression::binary_expr(
+                    Expression::Identifier(Identifier::new("tags")),
+                    BinaryOperator::NotContains,
+                    Expression::Literal(Literal::String("x".to_string().into())),
+                ),
+                BinaryOperator::Or,
+                Expression::Identifier(Identifier::new("notes")),
+            ),
+        );
+    }
+
+    #[test]
+    fn test_else() {
+        test_parser(
+            "a.b or c else false",
+            Expression::binary_expr(
+                Expression::binary_expr(
+                    Expression::select(
+                        Expression::Identifier(Identifier::new("a")),
+                        Identifier::new("b"),
+                    ),
+                    BinaryOperator::Or,
+                    Expression::Identifier(Identifier::new("c")),
+                ),
+                BinaryOperator::Else,
+                Expression::Literal(Literal::Boolean(false)),
+            ),
+        );
+        test_parser(
+            "a else b else 1 + 2",
+            Expression::binary_expr(
+                Expression::binary_expr(
+                    Expression::Identifier(Identifier::new("a")),
+                    BinaryOperator::Else,
+                    Expression::Identifier(Identifier::new("b")),
+                ),
+                BinaryOperator::Else,
+                Expression::binary_expr(
+                    Expression::Literal(Literal::Integer(1)),
+                    BinaryOperator::Add,
+                    Expression::Literal(Literal::Integer(2)),
+                ),
+            ),
+        );
+    }
+
     #[test]
     fn test_functions() {
+        test_parser(
+            "foobar(\n    a,\n    b,\n)",
+            Expression::call(
+                Identifier::new("foobar"),
+                vec![
+                    Expression::Identifier(Identifier::new("a")),
+                    Expression::Identifier(Identifier::new("b")),
+                ],
+            ),
+        );
         test_parser(
             "foobar()",
             Expression::call(Identifier::new("foobar"), vec![]),
@@ -291,6 +1042,515 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_index_and_slice() {
+        test_parser(
+            "list[0]",
+            Expression::index(
+                Expression::Identifier(Identifier::new("list")),
+                Expression::Literal(Literal::Integer(0)),
+            ),
+        );
+        test_parser(
+            "list[1:3]",
+            Expression::slice(
+                Expression::Identifier(Identifier::new("list")),
+                Some(Expression::Literal(Literal::Integer(1))),
+                Some(Expression::Literal(Literal::Integer(3))),
+            ),
+        );
+        test_parser(
+            "list[:2]",
+            Expression::slice(
+                Expression::Identifier(Identifier::new("list")),
+                None,
+                Some(Expression::Literal(Literal::Integer(2))),
+            ),
+        );
+        test_parser(
+            "list[ 1 : ]",
+            Expression::slice(
+                Expression::Identifier(Identifier::new("list")),
+                Some(Expression::Literal(Literal::Integer(1))),
+                None,
+            ),
+        );
+        test_parser(
+            "list[:]",
+            Expression::slice(Expression::Identifier(Identifier::new("list")), None, None),
+        );
+    }
+
+    #[test]
+    fn test_postfix_chain() {
+        test_parser(
+            r#"tfplan.resource_changes[0].change.after["tags"]"#,
+            Expression::index(
+                Expression::select(
+                    Expression::select(
+                        Expression::index(
+                            Expression::select(
+                                Expression::Identifier(Identifier::new("tfplan")),
+                                Identifier::new("resource_changes"),
+                            ),
+                            Expression::Literal(Literal::Integer(0)),
+                        ),
+                        Identifier::new("change"),
+                    ),
+                    Identifier::new("after"),
+                ),
+                Expression::Literal(Literal::String("tags".to_string().into())),
+            ),
+        );
+        test_parser(
+            r#"strings.has_prefix(x, "a")"#,
+            Expression::call(
+                Expression::select(
+                    Expression::Identifier(Identifier::new("strings")),
+                    Identifier::new("has_prefix"),
+                ),
+                vec![
+                    Expression::Identifier(Identifier::new("x")),
+                    Expression::Literal(Literal::String("a".to_string().into())),
+                ],
+            ),
+        );
+        test_parser(
+            "f(1)(2)[0]",
+            Expression::index(
+                Expression::call(
+                    Expression::call(
+                        Identifier::new("f"),
+                        vec![Expression::Literal(Literal::Integer(1))],
+                    ),
+                    vec![Expression::Literal(Literal::Integer(2))],
+                ),
+                Expression::Literal(Literal::Integer(0)),
+            ),
+        );
+    }
+
+    #[test]
+    fn test_postfix_binds_tighter_than_binary_operators() {
+        test_parser(
+            "a.b * c[1] + d.e",
+            Expression::binary_expr(
+                Expression::binary_expr(
+                    Expression::select(
+                        Expression::Identifier(Identifier::new("a")),
+                        Identifier::new("b"),
+                    ),
+                    BinaryOperator::Multiply,
+                    Expression::index(
+                        Expression::Identifier(Identifier::new("c")),
+                        Expression::Literal(Literal::Integer(1)),
+                    ),
+                ),
+                BinaryOperator::Add,
+                Expression::select(
+                    Expression::Identifier(Identifier::new("d")),
+                    Identifier::new("e"),
+                ),
+            ),
+        );
+        test_parser(
+            "x.y in list[1:]",
+            Expression::binary_expr(
+                Expression::select(
+                    Expression::Identifier(Identifier::new("x")),
+                    Identifier::new("y"),
+                ),
+                BinaryOperator::In,
+                Expression::slice(
+                    Expression::Identifier(Identifier::new("list")),
+                    Some(Expression::Literal(Literal::Integer(1))),
+                    None,
+                ),
+            ),
+        );
+    }
+
+    #[test]
+    fn test_list() {
+        test_parser("[]", Expression::list(vec![]));
+        test_parser("[ ]", Expression::list(vec![]));
+        test_parser(
+            r#"[1, 2, "a"]"#,
+            Expression::list(vec![
+                Expression::Literal(Literal::Integer(1)),
+                Expression::Literal(Literal::Integer(2)),
+                Expression::Literal(Literal::String("a".to_string().into())),
+            ]),
+        );
+        test_parser(
+            "[\n    a,\n    [b],\n]",
+            Expression::list(vec![
+                Expression::Identifier(Identifier::new("a")),
+                Expression::list(vec![Expression::Identifier(Identifier::new("b"))]),
+            ]),
+        );
+        test_parser(
+            "[1, 2][0]",
+            Expression::index(
+                Expression::list(vec![
+                    Expression::Literal(Literal::Integer(1)),
+                    Expression::Literal(Literal::Integer(2)),
+                ]),
+                Expression::Literal(Literal::Integer(0)),
+            ),
+        );
+    }
+
+    #[test]
+    fn test_map() {
+        test_parser("{}", Expression::map(vec![]));
+        test_parser(
+            r#"{"k": v, other: 1}"#,
+            Expression::map(vec![
+                (
+                    Expression::Literal(Literal::String("k".to_string().into())),
+                    Expression::Identifier(Identifier::new("v")),
+                ),
+                (
+                    Expression::Identifier(Identifier::new("other")),
+                    Expression::Literal(Literal::Integer(1)),
+                ),
+            ]),
+        );
+        test_parser(
+            "{\n    1 + 1: [a],\n    \"nested\": {\"x\": f(y)},\n}",
+            Expression::map(vec![
+                (
+                    Expression::binary_expr(
+                        Expression::Literal(Literal::Integer(1)),
+                        BinaryOperator::Add,
+                        Expression::Literal(Literal::Integer(1)),
+                    ),
+                    Expression::list(vec![Expression::Identifier(Identifier::new("a"))]),
+                ),
+                (
+                    Expression::Literal(Literal::String("nested".to_string().into())),
+                    Expression::map(vec![(
+                        Expression::Literal(Literal::String("x".to_string().into())),
+                        Expression::call(
+                            Identifier::new("f"),
+                            vec![Expression::Identifier(Identifier::new("y"))],
+                        ),
+                    )]),
+                ),
+            ]),
+        );
+    }
+
+    #[test]
+    fn test_function() {
+        test_parser(
+            "func() {}",
+            Expression::function(vec![], Statement::block(vec![])),
+        );
+        test_parser(
+            "func(a, b) {\n    x = a + b\n    return x\n}",
+            Expression::function(
+                vec![Identifier::new("a"), Identifier::new("b")],
+                Statement::block(vec![
+                    Statement::assignment(
+                        Expression::Identifier(Identifier::new("x")),
+                        Expression::binary_expr(
+                            Expression::Identifier(Identifier::new("a")),
+                            BinaryOperator::Add,
+                            Expression::Identifier(Identifier::new("b")),
+                        ),
+                    ),
+                    Statement::Return(Some(Expression::Identifier(Identifier::new("x")).into())),
+                ]),
+            ),
+        );
+        test_parser(
+            "func(f) { return func(x) { return f(x) } }",
+            Expression::function(
+                vec![Identifier::new("f")],
+                Statement::block(vec![Statement::Return(Some(
+                    Expression::function(
+                        vec![Identifier::new("x")],
+                        Statement::block(vec![Statement::Return(Some(
+                            Expression::call(
+                                Identifier::new("f"),
+                                vec![Expression::Identifier(Identifier::new("x"))],
+                            )
+                            .into(),
+                        ))]),
+                    )
+                    .into(),
+                ))]),
+            ),
+        );
+        test_parser(
+            "funcs(a)",
+            Expression::call(
+                Identifier::new("funcs"),
+                vec![Expression::Identifier(Identifier::new("a"))],
+            ),
+        );
+    }
+
+    #[test]
+    fn test_function_errors() {
+        test_parser::<Expression, &str>("func(a, b, a) {}", "duplicate parameter `a`");
+        let (_, errors) = parse::<Expression>("func(a, a) {}", FileId(0));
+        let spans = errors.iter().map(|e| e.span().start..e.span().end);
+        assert_eq!(spans.collect::<Vec<_>>(), vec![8..9]);
+
+        // The diagnostic points at the `break` rather than the whole body
+        let (_, errors) = parse::<Expression>(
+            "func() {
+  if x { break }
+}",
+            FileId(0),
+        );
+        let spans = errors.iter().map(|e| e.span().start..e.span().end);
+        assert_eq!(spans.collect::<Vec<_>>(), vec![18..23]);
+    }
+
+    #[test]
+    fn test_rule() {
+        test_parser(
+            "rule { true }",
+            Expression::rule(None, Expression::Literal(Literal::Boolean(true))),
+        );
+        test_parser(
+            "rule when x { y }",
+            Expression::rule(
+                Some(Expression::Identifier(Identifier::new("x"))),
+                Expression::Identifier(Identifier::new("y")),
+            ),
+        );
+        test_parser(
+            "rule when a == 1 {\n    is_tagged and\n    is_private\n}",
+            Expression::rule(
+                Some(Expression::binary_expr(
+                    Expression::Identifier(Identifier::new("a")),
+                    BinaryOperator::Equals,
+                    Expression::Literal(Literal::Integer(1)),
+                )),
+                Expression::binary_expr(
+                    Expression::Identifier(Identifier::new("is_tagged")),
+                    BinaryOperator::And,
+                    Expression::Identifier(Identifier::new("is_private")),
+                ),
+            ),
+        );
+    }
+
+    #[test]
+    fn test_quantifiers() {
+        test_parser(
+            "all coll as k, v { v > 1 }",
+            Expression::quantifier(
+                QuantifierType::All,
+                Expression::Identifier(Identifier::new("coll")),
+                Some(Identifier::new("k")),
+                Identifier::new("v"),
+                Expression::binary_expr(
+                    Expression::Identifier(Identifier::new("v")),
+                    BinaryOperator::GreaterThan,
+                    Expression::Literal(Literal::Integer(1)),
+                ),
+            ),
+        );
+        test_parser(
+            "any coll as v {\n    v\n}",
+            Expression::quantifier(
+                QuantifierType::Any,
+                Expression::Identifier(Identifier::new("coll")),
+                None,
+                Identifier::new("v"),
+                Expression::Identifier(Identifier::new("v")),
+            ),
+        );
+        test_parser(
+            "filter coll as v { v }",
+            Expression::quantifier(
+                QuantifierType::Filter,
+                Expression::Identifier(Identifier::new("coll")),
+                None,
+                Identifier::new("v"),
+                Expression::Identifier(Identifier::new("v")),
+            ),
+        );
+        test_parser(
+            "map f(x) as v { g(v) }",
+            Expression::quantifier(
+                QuantifierType::Map,
+                Expression::call(
+                    Identifier::new("f"),
+                    vec![Expression::Identifier(Identifier::new("x"))],
+                ),
+                None,
+                Identifier::new("v"),
+                Expression::call(
+                    Identifier::new("g"),
+                    vec![Expression::Identifier(Identifier::new("v"))],
+                ),
+            ),
+        );
+    }
+
+    #[test]
+    fn test_nested_quantifiers() {
+        test_parser(
+            "all a as x { any x as y { y } } and any_ok",
+            Expression::binary_expr(
+                Expression::quantifier(
+                    QuantifierType::All,
+                    Expression::Identifier(Identifier::new("a")),
+                    None,
+                    Identifier::new("x"),
+                    Expression::quantifier(
+                        QuantifierType::Any,
+                        Expression::Identifier(Identifier::new("x")),
+                        None,
+                        Identifier::new("y"),
+                        Expression::Identifier(Identifier::new("y")),
+                    ),
+                ),
+                BinaryOperator::And,
+                Expression::Identifier(Identifier::new("any_ok")),
+            ),
+        );
+    }
+
+    #[test]
+    fn test_interpolation() {
+        test_parser(
+            r#""instance ${r.address} uses ${r.type}""#,
+            Expression::Interpolation(vec![
+                StringPart::Text("instance ".to_string()),
+                StringPart::Expression(
+                    Expression::select(
+                        Expression::Identifier(Identifier::new("r")),
+                        Identifier::new("address"),
+                    )
+                    .into(),
+                ),
+                StringPart::Text(" uses ".to_string()),
+                StringPart::Expression(
+                    Expression::select(
+                        Expression::Identifier(Identifier::new("r")),
+                        Identifier::new("type"),
+                    )
+                    .into(),
+                ),
+            ]),
+        );
+        test_parser(
+            r#""${a}${"b"}""#,
+            Expression::Interpolation(vec![
+                StringPart::Expression(Expression::Identifier(Identifier::new("a")).into()),
+                StringPart::Expression(
+                    Expression::Literal(Literal::String("b".to_string().into())).into(),
+                ),
+            ]),
+        );
+        test_parser(
+            r#""no $ {interpolation} \${here}""#,
+            Expression::Literal(Literal::String(
+                "no $ {interpolation} ${here}".to_string().into(),
+            )),
+        );
+    }
+
+    #[test]
+    fn test_interpolation_recovery() {
+        // Each mistake is reported once, over the whole interpolation
+        test_recovery(
+            r#""a ${} b""#,
+            Expression::Interpolation(vec![
+                StringPart::Text("a ".to_string()),
+                StringPart::Expression(Expression::Error.into()),
+                StringPart::Text(" b".to_string()),
+            ]),
+            &["empty interpolation"],
+        );
+        test_recovery(
+            r#""a ${b""#,
+            Expression::Interpolation(vec![
+                StringPart::Text("a ".to_string()),
+                StringPart::Expression(Expression::Identifier(Identifier::new("b")).into()),
+            ]),
+            &["unterminated `${`"],
+        );
+        let (_, errors) = parse::<Expression>(r#""a ${}" + "a ${b""#, FileId(0));
+        let spans = errors.iter().map(|e| e.span().start..e.span().end);
+        assert_eq!(spans.collect::<Vec<_>>(), vec![3..6, 13..16]);
+        // A string left open is reported once, however many interpolations it opened
+        test_recovery(
+            r#""a ${b"#,
+            Expression::Literal(Literal::String("a ${b".to_string().into())),
+            &["unterminated string"],
+        );
+    }
+
+    #[test]
+    fn test_rule_names_are_identifiers() {
+        test_parser("rules", Expression::Identifier(Identifier::new("rules")));
+    }
+
+    #[test]
+    fn test_delimiter_recovery() {
+        test_recovery(
+            "[1, (2 +), [3 4]]",
+            Expression::list(vec![
+                Expression::Literal(Literal::Integer(1)),
+                Expression::Error,
+                Expression::Error,
+            ]),
+            &["found ) expected", "found 4 expected"],
+        );
+        test_recovery(
+            "f(a,, b).x",
+            Expression::select(
+                Expression::Call {
+                    func: Box::new(Expression::Identifier(Identifier::new("f")).into()),
+                    args: vec![Expression::Error.into()],
+                },
+                Identifier::new("x"),
+            ),
+            &["found , expected"],
+        );
+        test_recovery(
+            "rule { a + }",
+            Expression::rule(None, Expression::Error),
+            &["found } expected"],
+        );
+        // A failed call, index or slice keeps its statement together, rather than being
+        // left for the next statement to report
+        let assignments = |value: Expression| {
+            Statement::block(vec![
+                Statement::assignment(ident("x"), value),
+                Statement::assignment(ident("y"), int(3)),
+            ])
+        };
+        test_recovery(
+            "{\n  x = a[1 +]\n  y = 3\n}",
+            assignments(Expression::index(ident("a"), Expression::Error)),
+            &["found ] expected"],
+        );
+        test_recovery(
+            "{\n  x = a[1:2:3]\n  y = 3\n}",
+            assignments(Expression::index(ident("a"), Expression::Error)),
+            &["found : expected"],
+        );
+        // An unclosed call runs to the end of the enclosing block
+        test_recovery(
+            "{\n  x = f(a\n  y = 3\n}",
+            Statement::block(vec![Statement::assignment(
+                ident("x"),
+                Expression::call(Identifier::new("f"), vec![Expression::Error]),
+            )]),
+            &["found y expected"],
+        );
+    }
+
     impl From<Expression> for Expect<Expression> {
         fn from(value: Expression) -> Self {
             Expect::Something(value)
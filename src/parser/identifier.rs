@@ -1,5 +1,43 @@
-use crate::parser::{Parsable, ParsableError};
-use chumsky::{text, Parser};
+use crate::parser::{
+    select_token, unexpected, Parsable, ParsableError, ParserInput, Span, Spanned, Token,
+};
+use chumsky::prelude::*;
+
+/// Words with a meaning in the grammar, which cannot be used as identifiers
+pub(crate) const KEYWORDS: &[&str] = &[
+    "all",
+    "and",
+    "any",
+    "as",
+    "break",
+    "case",
+    "contains",
+    "continue",
+    "default",
+    "defined",
+    "else",
+    "empty",
+    "false",
+    "filter",
+    "for",
+    "func",
+    "if",
+    "import",
+    "in",
+    "is",
+    "map",
+    "matches",
+    "not",
+    "null",
+    "or",
+    "param",
+    "return",
+    "rule",
+    "true",
+    "undefined",
+    "when",
+    "xor",
+];
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct Identifier(pub String);
@@ -10,8 +48,112 @@ impl Identifier {
     }
 }
 
+fn reserved<'src>(name: &str, span: Span) -> Rich<'src, Token<'src>, Span> {
+    Rich::custom(
+        span,
+        format!(
+            "`{}` is a reserved keyword and cannot be used as a name",
+            name
+        ),
+    )
+}
+
 impl Parsable for Identifier {
-    fn parser<'src>() -> impl Parser<'src, &'src str, Self, ParsableError<'src>> {
-        text::ident().map(|s: &str| Identifier(s.to_string()))
+    fn parser<'src>() -> impl Parser<'src, ParserInput<'src>, Self, ParsableError<'src>> {
+        any().try_map(|token, span| match token {
+            Token::Ident(name) => Ok(Identifier::new(name)),
+            Token::Keyword(keyword) => Err(reserved(keyword, span)),
+            token => Err(unexpected(token, span)),
+        })
+    }
+}
+
+impl Identifier {
+    /// Parses a name being bound, such as an assignment target or a loop variable.
+    /// The position is unambiguous, so a keyword is reported as an error rather than
+    /// rejected, which keeps the diagnostic from being lost to backtracking.
+    pub(crate) fn name<'src>(
+    ) -> impl Parser<'src, ParserInput<'src>, Spanned<Self>, ParsableError<'src>> + Clone {
+        select_token! {
+            Token::Ident(name) => (name, false),
+            Token::Keyword(keyword) => (keyword, true),
+        }
+        .validate(|(name, reserved_word), e, emitter| {
+            if reserved_word {
+                emitter.emit(reserved(name, e.span()))
+            }
+            Spanned::new(Identifier::new(name), e.span())
+        })
+    }
+
+    /// Parses a reserved word where an operand is expected, as in `x = default` or
+    /// `f(in)`. Words that can start an expression, such as `true` or `func`, are left
+    /// to the expression grammar. The rest are reported as misused names rather than
+    /// rejected, which keeps the diagnostic from being lost to backtracking.
+    pub(crate) fn reserved_operand<'src>(
+    ) -> impl Parser<'src, ParserInput<'src>, (), ParsableError<'src>> + Clone {
+        const OPERANDS: &[&str] = &[
+            "all",
+            "any",
+            "false",
+            "filter",
+            "func",
+            "map",
+            "not",
+            "null",
+            "rule",
+            "true",
+            "undefined",
+        ];
+        select_token! {
+            Token::Keyword(keyword) if !OPERANDS.contains(&keyword) => keyword,
+        }
+        .validate(|keyword, e, emitter| emitter.emit(reserved(keyword, e.span())))
+    }
+
+    /// Parses the name of a field in a selector expression such as `a.b`. Field names
+    /// are never ambiguous, so keywords are allowed.
+    pub(crate) fn field<'src>() -> impl Parser<'src, ParserInput<'src>, Self, ParsableError<'src>> {
+        select_token! {
+            Token::Ident(name) | Token::Keyword(name) => Identifier::new(name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::{test_parser, Expect, Identifier};
+
+    #[test]
+    fn test_parse() {
+        test_parser("foobar", Identifier::new("foobar"));
+        test_parser("snake_case2", Identifier::new("snake_case2"));
+        test_parser("index", Identifier::new("index"));
+        test_parser("trueish", Identifier::new("trueish"));
+    }
+
+    #[test]
+    fn test_unicode() {
+        test_parser("_private", Identifier::new("_private"));
+        test_parser("région", Identifier::new("région"));
+        test_parser("名前", Identifier::new("名前"));
+        test_parser("Δx_1", Identifier::new("Δx_1"));
+        test_parser::<Identifier, &str>("€uro", "unexpected character `€`");
+    }
+
+    #[test]
+    fn test_reserved_keywords() {
+        test_parser::<Identifier, &str>(
+            "if",
+            "`if` is a reserved keyword and cannot be used as a name",
+        );
+        test_parser::<Identifier, &str>("contains", "`contains` is a reserved keyword");
+        test_parser::<Identifier, &str>("null", "`null` is a reserved keyword");
+    }
+
+    impl From<Identifier> for Expect<Identifier> {
+        fn from(value: Identifier) -> Self {
+            Expect::Something(value)
+        }
     }
 }
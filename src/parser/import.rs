@@ -0,0 +1,108 @@
+use crate::parser::{
+    is_identifier, keyword, Identifier, Literal, Parsable, ParsableError, ParserInput, Spanned,
+};
+use chumsky::prelude::*;
+use std::sync::Arc;
+
+/// An import declaration, e.g. `import "tfplan/v2" as tfplan`
+#[derive(Debug, PartialEq, Clone)]
+pub struct Import {
+    pub name: Arc<String>,
+    pub alias: Option<Spanned<Identifier>>,
+}
+
+impl Import {
+    pub fn new<T: Into<String>>(name: T, alias: Option<Identifier>) -> Self {
+        Import {
+            name: Arc::new(name.into()),
+            alias: alias.map(Spanned::from),
+        }
+    }
+
+    /// The name the import is bound to in the policy, which is the alias if one
+    /// was given and the last segment of the import path otherwise, e.g. `v2` for
+    /// `import "tfplan/v2"`.
+    pub fn binding(&self) -> &str {
+        match &self.alias {
+            Some(alias) => &alias.0,
+            None => self.name.rsplit('/').next().unwrap_or_default(),
+        }
+    }
+}
+
+impl Parsable for Import {
+    fn parser<'src>() -> impl Parser<'src, ParserInput<'src>, Self, ParsableError<'src>> {
+        let name = Literal::parser().try_map(|literal, span| match literal {
+            Literal::String(name) => Ok(name),
+            _ => Err(Rich::custom(span, "expected a string naming the import")),
+        });
+
+        keyword("import")
+            .ignore_then(name)
+            .then(keyword("as").ignore_then(Identifier::name()).or_not())
+            .validate(|(name, alias), e, emitter| {
+                let import = Import { name, alias };
+                if import.alias.is_none() && !is_identifier(import.binding()) {
+                    emitter.emit(Rich::custom(
+                        e.span(),
+                        format!(
+                            "import \"{}\" must be bound with `as <name>`, as `{}` is not a \
+                             valid identifier",
+                            import.name,
+                            import.binding()
+                        ),
+                    ));
+                }
+                import
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{test_parser, Expect};
+
+    #[test]
+    fn test_import() {
+        test_parser(r#"import "strings""#, Import::new("strings", None));
+        test_parser(
+            r#"import "tfplan/v2" as tfplan"#,
+            Import::new("tfplan/v2", Some(Identifier::new("tfplan"))),
+        );
+        test_parser(
+            "import /* plan */ \"tfplan/v2\"\n    as tfplan",
+            Import::new("tfplan/v2", Some(Identifier::new("tfplan"))),
+        );
+        test_parser::<Import, &str>("import 42", "expected a string naming the import");
+        test_parser::<Import, &str>(
+            r#"import "aws-sdk""#,
+            "import \"aws-sdk\" must be bound with `as <name>`, as `aws-sdk` is not a valid \
+             identifier",
+        );
+        test_parser::<Import, &str>(
+            r#"import "tfplan/""#,
+            "import \"tfplan/\" must be bound with `as <name>`",
+        );
+        test_parser(
+            r#"import "aws-sdk" as aws"#,
+            Import::new("aws-sdk", Some(Identifier::new("aws"))),
+        );
+    }
+
+    #[test]
+    fn test_binding() {
+        assert_eq!(Import::new("strings", None).binding(), "strings");
+        assert_eq!(Import::new("tfplan/v2", None).binding(), "v2");
+        assert_eq!(
+            Import::new("tfplan/v2", Some(Identifier::new("tfplan"))).binding(),
+            "tfplan"
+        );
+    }
+
+    impl From<Import> for Expect<Import> {
+        fn from(value: Import) -> Self {
+            Expect::Something(value)
+        }
+    }
+}
@@ -1,4 +1,5 @@
-use crate::parser::Parsable;
+use crate::parser::{op, select_token, LexError, Parsable, ParsableError, ParserInput, Token};
+use chumsky::input::InputRef;
 use chumsky::prelude::*;
 use std::sync::Arc;
 use strum_macros::EnumString;
@@ -13,8 +14,90 @@ pub enum Literal {
     Boolean(bool),
 }
 
+/// The magnitude of the smallest integer, which can only be written negated
+const MIN_INTEGER_MAGNITUDE: u64 = 1 << 63;
+
 impl Parsable for Literal {
-    fn parser<'src>() -> impl Parser<'src, &'src str, Self, extra::Err<Rich<'src, char>>> {
+    fn parser<'src>() -> impl Parser<'src, ParserInput<'src>, Self, ParsableError<'src>> {
+        let integer = select_token! { Token::Integer(magnitude) => magnitude }.validate(
+            |magnitude, e, emitter| {
+                i64::try_from(magnitude).unwrap_or_else(|_| {
+                    emitter.emit(Rich::custom(e.span(), too_large()));
+                    i64::MAX
+                })
+            },
+        );
+
+        choice((
+            integer.map(Literal::Integer),
+            select_token! {
+                Token::Literal(literal) => literal,
+                Token::Keyword("true") => Literal::Boolean(true),
+                Token::Keyword("false") => Literal::Boolean(false),
+                Token::Keyword("null") => Literal::Null,
+                Token::Keyword("undefined") => Literal::Undefined,
+            },
+            Self::min_integer(),
+        ))
+    }
+}
+
+fn too_large() -> String {
+    "integer literal is too large for a 64-bit integer".to_string()
+}
+
+/// Converts the digits of an integer literal to its magnitude, ignoring `_` separators
+fn magnitude(digits: &str, radix: u32) -> Result<u64, String> {
+    let digits = digits.replace('_', "");
+    if let Some(c) = digits.chars().find(|c| !c.is_digit(radix)) {
+        let base = match radix {
+            2 => "binary",
+            8 => "octal",
+            _ => "decimal",
+        };
+        return Err(format!("invalid digit '{}' in {} literal", c, base));
+    }
+    match u64::from_str_radix(&digits, radix) {
+        Ok(magnitude) if magnitude <= MIN_INTEGER_MAGNITUDE => Ok(magnitude),
+        _ => Err(too_large()),
+    }
+}
+
+impl Literal {
+    /// Parses `-9223372036854775808`, whose magnitude is out of range for a positive
+    /// integer and so cannot be parsed as a negated literal
+    pub(crate) fn min_integer<'src>(
+    ) -> impl Parser<'src, ParserInput<'src>, Self, ParsableError<'src>> + Clone {
+        op("-")
+            .ignore_then(just(Token::Integer(MIN_INTEGER_MAGNITUDE)))
+            .to(Literal::Integer(i64::MIN))
+    }
+
+    /// Lexes a number or string literal. Booleans, `null` and `undefined` are keywords,
+    /// which the lexer recognizes along with every other reserved word. Integers are
+    /// lexed as their magnitude, leaving the parser to check that they fit in an `i64`.
+    pub(crate) fn lexer<'src>() -> impl Parser<'src, &'src str, Token<'src>, LexError<'src>> + Clone
+    {
+        // Digits may be separated by single underscores, e.g. 1_000_000
+        let digits = |radix: u32| {
+            text::digits(radix)
+                .then(
+                    any()
+                        .filter(move |c: &char| c.is_digit(radix) || *c == '_')
+                        .repeated(),
+                )
+                .to_slice()
+                .validate(|digits: &str, e, emitter| {
+                    if digits.contains("__") || digits.ends_with('_') {
+                        emitter.emit(Rich::custom(
+                            e.span(),
+                            "`_` must separate digits in a number",
+                        ))
+                    }
+                    digits
+                })
+        };
+
         // Floating-point Literals
         // A floating-point literal is a decimal representation of a floating-point constant. It has
         // an integer part, a decimal point, a fractional part, and an exponent part. The integer and
@@ -27,7 +110,7 @@ impl Parsable for Literal {
         // float_lit = decimals "." [ decimals ] [ exponent ] |
         //             decimals exponent |
         //             "." decimals [ exponent ] .
-        // decimals  = decimal_digit { decimal_digit } .
+        // decimals  = decimal_digit { [ "_" ] decimal_digit } .
         // exponent  = ( "e" | "E" ) [ "+" | "-" ] decimals .
         //
         //  0.
@@ -39,165 +122,307 @@ impl Parsable for Literal {
         //  1E6
         //  .25
         //  .12345E+5
-        let exponent = just('e')
-            .or(just('E'))
-            .then(just('+').or(just('-')).or_not())
-            .then(text::digits(10));
+        //  1_000.5
+        let exponent = one_of("eE")
+            .then(one_of("+-").or_not())
+            .then(digits(10).or_not())
+            .validate(|((_, _), digits), e, emitter| {
+                if digits.is_none() {
+                    emitter.emit(Rich::custom(
+                        e.span(),
+                        "malformed exponent: expected digits after `e`",
+                    ))
+                }
+            });
         let float = choice((
-            text::digits(10)
+            digits(10)
                 .then_ignore(just('.'))
-                .then(text::digits(10).or_not())
+                .then(digits(10).or_not())
                 .then(exponent.or_not())
-                .to_slice()
-                .from_str()
-                .unwrapped()
-                .map(|v| Literal::Float(v)),
-            text::digits(10)
-                .then(exponent)
-                .to_slice()
-                .from_str()
-                .unwrapped()
-                .map(|v| Literal::Float(v)),
+                .ignored(),
+            digits(10).then(exponent).ignored(),
             just('.')
-                .ignore_then(text::digits(10))
+                .ignore_then(digits(10))
                 .then(exponent.or_not())
-                .to_slice()
-                .from_str()
-                .unwrapped()
-                .map(|v| Literal::Float(v)),
-        ));
+                .ignored(),
+        ))
+        .to_slice()
+        .validate(|s: &str, e, emitter| {
+            // A malformed exponent has already been reported, so only the mantissa is kept
+            let s = s.replace('_', "");
+            let value = s
+                .parse::<f64>()
+                .or_else(|_| s.trim_end_matches(['e', 'E', '+', '-']).parse())
+                .unwrap_or_default();
+            if value.is_infinite() {
+                emitter.emit(Rich::custom(
+                    e.span(),
+                    "float literal is too large for a 64-bit float",
+                ))
+            }
+            Literal::Float(value)
+        });
 
         // Integer Literals
         // An integer literal is a sequence of digits representing an integer constant.
-        // An optional prefix sets a non-decimal base: 0 for octal, 0x or 0X for hexadecimal.
-        // In hexadecimal literals, letters a-f and A-F represents values 10 through 15.
+        // An optional prefix sets a non-decimal base: 0 for octal, 0b or 0B for binary, 0x or
+        // 0X for hexadecimal. In hexadecimal literals, letters a-f and A-F represents values
+        // 10 through 15. An underscore may appear after a base prefix or between digits.
         //
         // Integers are signed 64-bit values (-9223372036854775808 to 9223372036854775807).
         //
-        // int_lit     = decimal_lit | octal_lit | hex_lit .
-        // decimal_lit = ( "1" … "9" ) { decimal_digit } .
-        // octal_lit   = "0" { octal_digit } .
-        // hex_lit     = "0" ( "x" | "X" ) hex_digit { hex_digit } .
+        // int_lit     = decimal_lit | binary_lit | octal_lit | hex_lit .
+        // decimal_lit = "0" | ( "1" … "9" ) [ [ "_" ] decimal_digits ] .
+        // binary_lit  = "0" ( "b" | "B" ) [ "_" ] binary_digits .
+        // octal_lit   = "0" [ "_" ] octal_digits .
+        // hex_lit     = "0" ( "x" | "X" ) [ "_" ] hex_digits .
         //
         // 42
+        // 4_2
         // 0600
+        // 0b1010
         // 0xBadFace
-        // 170141183460469231731687303715884105727
-        let decimal = text::int(10).map(|s: &str| Literal::Integer(s.parse().unwrap()));
-        let octal = just('0').then(text::digits(8)).to_slice().map(|s: &str| {
-            if s == "0" {
-                Literal::Integer(0)
-            } else {
-                i64::from_str_radix(s, 8).map(Literal::Integer).unwrap()
-            }
+        // 0x_67_7a_2f_cc_40_c6
+        // 170141183460469231731687303715884105727  // too large
+        //
+        // Digits that are invalid for the base are lexed anyway, so that `099` is reported
+        // as a bad octal literal rather than as two numbers.
+        let prefixed = |prefix: &'static str, radix: u32| {
+            just('0')
+                .ignore_then(one_of(prefix))
+                .ignore_then(just('_').or_not())
+                .ignore_then(digits(radix.max(10)).or_not())
+                .map(move |digits| (digits, radix))
+        };
+        let integer = choice((
+            prefixed("xX", 16),
+            prefixed("bB", 2),
+            just('0')
+                .ignore_then(just('_').or_not())
+                .ignore_then(digits(10))
+                .map(|digits| (Some(digits), 8)),
+            digits(10).map(|digits| (Some(digits), 10)),
+        ))
+        .validate(|(digits, radix), e, emitter| {
+            let magnitude = match digits {
+                Some(digits) => magnitude(digits, radix),
+                None => Err("expected digits after the base prefix".to_string()),
+            };
+            magnitude.unwrap_or_else(|message| {
+                emitter.emit(Rich::custom(e.span(), message));
+                0
+            })
         });
-        let hex = just("0x")
-            .or(just("0X"))
-            .ignore_then(text::digits(16).to_slice())
-            .map(|s: &str| i64::from_str_radix(s, 16).map(Literal::Integer).unwrap());
-        let integer = choice((hex, octal, decimal));
-
-        // Boolean Literals
-        let boolean = choice((
-            just::<&str, &str, extra::Err<Rich<'src, char>>>("true")
-                .map(|_| Literal::Boolean(true)),
-            just::<&str, &str, extra::Err<Rich<'src, char>>>("false")
-                .map(|_| Literal::Boolean(false)),
-        ));
-
-        // String Literals
-        // let escape_sequence = choice((
-        //     just("\\a").map(|_| '\x07'),
-        //     just("\\b").map(|_| '\x08'),
-        //     just("\\f").map(|_| '\x0C'),
-        //     just("\\n").map(|_| '\x0A'),
-        //     just("\\r").map(|_| '\x0D'),
-        //     just("\\t").map(|_| '\x09'),
-        //     just("\\v").map(|_| '\x0B'),
-        //     just("\\\\").map(|_| '\\'),
-        //     just("\\\"").map(|_| '"'),
-        //     just("\\x").ignore_then(
-        //         text::digits(16)
-        //             .exactly(2)
-        //             .collect::<String>()
-        //             .validate(|s, span, emitter| {
-        //                 if u8::from_str_radix(&s, 16).is_err() {
-        //                     emitter.emit(Rich::custom(
-        //                         span,
-        //                         format!("Invalid hex byte value: \\x{}", s),
-        //                     ));
-        //                 }
-        //                 s // Return the string so it can be used in the next map
-        //             })
-        //             .map(|s| u8::from_str_radix(&s, 16).unwrap() as char),
-        //     ),
-        //     just("\\u").ignore_then(
-        //         text::digits(16)
-        //             .exactly(4)
-        //             .collect::<String>()
-        //             .validate(|s, span, emitter| {
-        //                 if u16::from_str_radix(&s, 16).is_err() {
-        //                     emitter.emit(Rich::custom(
-        //                         span,
-        //                         format!("Invalid Unicode value: \\u{}", s),
-        //                     ));
-        //                 }
-        //                 s // Return the string so it can be used in the next map
-        //             })
-        //             .map(|s| char::from_u32(u32::from_str_radix(&s, 16).unwrap()).unwrap()),
-        //     ),
-        //     just("\\U").ignore_then(
-        //         text::digits(16)
-        //             .exactly(8)
-        //             .collect::<String>()
-        //             .validate(|s, span, emitter| {
-        //                 if let Ok(value) = u32::from_str_radix(&s, 16) {
-        //                     if char::from_u32(value).is_none() {
-        //                         emitter.emit(Rich::custom(
-        //                             span,
-        //                             format!("Invalid Unicode value: \\U{}", s),
-        //                         ));
-        //                     }
-        //                 } else {
-        //                     emitter.emit(Rich::custom(
-        //                         span,
-        //                         format!("Invalid Unicode value: \\U{}", s),
-        //                     ));
-        //                 }
-        //                 s // Return the string so it can be used in the next map
-        //             })
-        //             .map(|s| char::from_u32(u32::from_str_radix(&s, 16).unwrap()).unwrap()),
-        //     ),
-        //     just("\\").ignore_then(
-        //         text::digits(8)
-        //             .exactly(3)
-        //             .collect::<String>()
-        //             .validate(|s, span, emitter| {
-        //                 if u8::from_str_radix(&s, 8).is_err() {
-        //                     emitter.emit(Rich::custom(
-        //                         span,
-        //                         format!("Invalid octal byte value: \\{}", s),
-        //                     ));
-        //                 }
-        //                 s // Return the string so it can be used in the next map
-        //             })
-        //             .map(|s| u8::from_str_radix(&s, 8).unwrap() as char),
-        //     ),
-        // ));
 
+        // A string containing `${` is an interpolated string, which the lexer handles
+        // separately as it holds tokens of its own
         let string = just('"')
-            .ignore_then(none_of('"').repeated().collect::<String>())
+            .ignore_then(Self::string_char().repeated().collect::<String>())
             .then_ignore(just('"'))
             .map(|v| Literal::String(Arc::new(v)));
 
-        let undefined = just::<&str, &str, extra::Err<Rich<'src, char>>>("undefined")
-            .map(|_| Literal::Undefined);
-        let null = just::<&str, &str, extra::Err<Rich<'src, char>>>("null").map(|_| Literal::Null);
+        // Raw strings are written between backticks and may span lines. Backslashes have
+        // no special meaning, which suits regular expressions.
+        //
+        // raw_string_lit = "`" { unicode_char | newline } "`" .
+        let raw_string = just('`')
+            .ignore_then(none_of('`').repeated().to_slice())
+            .then_ignore(just('`'))
+            .map(|v: &str| Literal::String(Arc::new(v.to_string())));
+
+        // Heredocs start with `<<` and a marker, and hold every following line up to one
+        // containing only the marker. Indentation shared by the lines is removed, so the
+        // text can be indented along with the surrounding code. Each line keeps its
+        // newline.
+        //
+        //     doc = <<EOF
+        //         {"key": "value"}
+        //         EOF
+        let heredoc = just("<<")
+            .ignore_then(heredoc_body())
+            .validate(|result, e, emitter| {
+                result.unwrap_or_else(|message| {
+                    emitter.emit(Rich::custom(e.span(), message));
+                    String::new()
+                })
+            })
+            .map(|v| Literal::String(Arc::new(v)));
+
+        choice((
+            float.map(Token::Literal),
+            integer.map(Token::Integer),
+            string.map(Token::Literal),
+            raw_string.map(Token::Literal),
+            heredoc.map(Token::Literal),
+        ))
+    }
 
-        choice((float, integer, string, boolean, undefined, null))
+    /// Lexes a single character of a double-quoted string, resolving escape sequences.
+    /// The `${` that starts an interpolation is not a string character.
+    pub(crate) fn string_char<'src>() -> impl Parser<'src, &'src str, char, LexError<'src>> + Clone
+    {
+        // String Literals
+        // A string literal is a sequence of characters between double quotes. Within the quotes,
+        // any character may appear except an unescaped double quote or backslash. Backslash
+        // escapes allow arbitrary values to be encoded:
+        //
+        // \a   U+0007 alert or bell
+        // \b   U+0008 backspace
+        // \f   U+000C form feed
+        // \n   U+000A line feed or newline
+        // \r   U+000D carriage return
+        // \t   U+0009 horizontal tab
+        // \v   U+000B vertical tab
+        // \\   U+005C backslash
+        // \"   U+0022 double quote
+        // \$   U+0024 dollar sign, so that `\${` is not an interpolation
+        //
+        // \x followed by exactly two hexadecimal digits, \ followed by exactly three octal
+        // digits, and \u and \U followed by exactly four and eight hexadecimal digits
+        // respectively. The octal and \x escapes encode a single byte value, which must be
+        // ASCII (at most \x7f or \177) since strings are UTF-8. The \u and \U escapes
+        // encode a Unicode code point.
+        //
+        // string_lit       = `"` { unicode_value | byte_value } `"` .
+        // unicode_value    = unicode_char | little_u_value | big_u_value | escaped_char .
+        // byte_value       = octal_byte_value | hex_byte_value .
+        // octal_byte_value = `\` octal_digit octal_digit octal_digit .
+        // hex_byte_value   = `\` "x" hex_digit hex_digit .
+        // little_u_value   = `\` "u" hex_digit hex_digit hex_digit hex_digit .
+        // big_u_value      = `\` "U" hex_digit hex_digit hex_digit hex_digit
+        //                            hex_digit hex_digit hex_digit hex_digit .
+        // escaped_char     = `\` ( "a" | "b" | "f" | "n" | "r" | "t" | "v" | `\` | `"` | "$" ) .
+        let hex_digits = |n| {
+            any()
+                .filter(|c: &char| c.is_ascii_hexdigit())
+                .repeated()
+                .exactly(n)
+                .to_slice()
+        };
+        let code_point = |s: &str| {
+            u32::from_str_radix(s, 16)
+                .ok()
+                .and_then(char::from_u32)
+                .ok_or_else(|| format!("invalid Unicode code point: {}", s))
+        };
+        // A byte above 0x7f is not valid UTF-8 on its own
+        let ascii_byte = |value: u32, escape: String| {
+            char::from_u32(value).filter(char::is_ascii).ok_or_else(|| {
+                format!(
+                    "invalid byte value: {} is not ASCII, use \\u{:04X} for the character \
+                         U+{:04X}",
+                    escape, value, value
+                )
+            })
+        };
+        let escape_sequence = just('\\')
+            .ignore_then(choice((
+                just('a').to(Ok('\x07')),
+                just('b').to(Ok('\x08')),
+                just('f').to(Ok('\x0C')),
+                just('n').to(Ok('\x0A')),
+                just('r').to(Ok('\x0D')),
+                just('t').to(Ok('\x09')),
+                just('v').to(Ok('\x0B')),
+                just('\\').to(Ok('\\')),
+                just('"').to(Ok('"')),
+                just('$').to(Ok('$')),
+                just('x').ignore_then(hex_digits(2)).map(move |s: &str| {
+                    ascii_byte(u32::from_str_radix(s, 16).unwrap(), format!("\\x{}", s))
+                }),
+                just('u').ignore_then(hex_digits(4)).map(code_point),
+                just('U').ignore_then(hex_digits(8)).map(code_point),
+                text::digits(8)
+                    .exactly(3)
+                    .to_slice()
+                    .map(move |s: &str| match u8::from_str_radix(s, 8) {
+                        Ok(byte) => ascii_byte(byte.into(), format!("\\{}", s)),
+                        Err(_) => Err(format!("invalid octal byte value: \\{}", s)),
+                    }),
+                any().map(|c| Err(format!("invalid escape sequence: \\{}", c))),
+            )))
+            .validate(|result, e, emitter| {
+                result.unwrap_or_else(|message| {
+                    emitter.emit(Rich::custom(e.span(), message));
+                    char::REPLACEMENT_CHARACTER
+                })
+            });
+
+        none_of("\\\"").and_is(just("${").not()).or(escape_sequence)
     }
 }
 
+/// Lexes a heredoc after its `<<`. Input that does not start with a marker and a line
+/// break is rejected, while a missing closing marker is returned as an error message.
+fn heredoc_body<'src>(
+) -> impl Parser<'src, &'src str, Result<String, String>, LexError<'src>> + Clone {
+    custom(|inp: &mut InputRef<'src, '_, &'src str, LexError<'src>>| {
+        let before = inp.offset();
+        let mut marker = String::new();
+        while let Some(c) = inp
+            .peek()
+            .filter(|c| c.is_ascii_alphanumeric() || *c == '_')
+        {
+            inp.skip();
+            marker.push(c);
+        }
+        while let Some(' ' | '\t') = inp.peek() {
+            inp.skip();
+        }
+        if inp.peek() == Some('\r') {
+            inp.skip();
+        }
+        if marker.is_empty() || inp.next() != Some('\n') {
+            return Err(Rich::custom(
+                inp.span_since(before),
+                "expected a heredoc marker followed by a line break",
+            ));
+        }
+
+        let mut lines = vec![];
+        loop {
+            let mut line = String::new();
+            while let Some(c) = inp.peek().filter(|c| *c != '\n') {
+                inp.skip();
+                line.push(c);
+            }
+            if line.trim() == marker {
+                return Ok(Ok(dedent(&lines)));
+            }
+            if inp.next().is_none() {
+                return Ok(Err(format!(
+                    "unterminated heredoc: expected a line containing only `{}`",
+                    marker
+                )));
+            }
+            lines.push(line.strip_suffix('\r').unwrap_or(&line).to_string());
+        }
+    })
+}
+
+/// Joins the lines of a heredoc after removing the indentation shared by every line
+/// that is not blank. Tabs and spaces are not interchangeable: only a prefix that is
+/// identical on every such line is removed.
+fn dedent(lines: &[String]) -> String {
+    let indent = lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| &line[..line.len() - line.trim_start_matches([' ', '\t']).len()])
+        .reduce(|common, indent| {
+            let shared = common
+                .bytes()
+                .zip(indent.bytes())
+                .take_while(|(a, b)| a == b)
+                .count();
+            &common[..shared]
+        })
+        .map_or(0, str::len);
+    lines
+        .iter()
+        .map(|line| format!("{}\n", line.get(indent..).unwrap_or("")))
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -211,6 +436,14 @@ mod tests {
         test_parser("1E6", Literal::Float(1e6));
         test_parser(".25", Literal::Float(0.25));
         test_parser(".12345E+5", Literal::Float(12345.0));
+        test_parser("1_000.000_5", Literal::Float(1000.0005));
+    }
+
+    #[test]
+    fn test_parse_invalid_float() {
+        test_parser::<Literal, &str>("1e", "malformed exponent");
+        test_parser::<Literal, &str>("2.5E+", "malformed exponent");
+        test_parser::<Literal, &str>("1e999", "float literal is too large");
     }
 
     #[test]
@@ -219,11 +452,38 @@ mod tests {
         test_parser("0", Literal::Integer(0));
         // Octal
         test_parser("076", Literal::Integer(62));
-        test_parser::<Literal, &str>("099", "found end of input");
+        test_parser("0_76", Literal::Integer(62));
+        test_parser::<Literal, &str>("099", "invalid digit '9' in octal literal");
         // Hexadecimal
         test_parser("0x1A3F", Literal::Integer(0x1A3F));
         test_parser::<Literal, &str>("0x9X", "found X expected end of input");
         test_parser("0X1A3F", Literal::Integer(0x1A3F));
+        test_parser("0x_67_7a", Literal::Integer(0x677a));
+        test_parser::<Literal, &str>("0x", "expected digits after the base prefix");
+        // Binary
+        test_parser("0b1010", Literal::Integer(10));
+        test_parser("0B_1111_0000", Literal::Integer(0xF0));
+        test_parser::<Literal, &str>("0b102", "invalid digit '2' in binary literal");
+        // Separators
+        test_parser("1_000_000", Literal::Integer(1_000_000));
+        test_parser::<Literal, &str>("1__0", "`_` must separate digits");
+        test_parser::<Literal, &str>("1_", "`_` must separate digits");
+    }
+
+    #[test]
+    fn test_integer_range() {
+        test_parser("9223372036854775807", Literal::Integer(i64::MAX));
+        test_parser("-9223372036854775808", Literal::Integer(i64::MIN));
+        test_parser::<Literal, &str>(
+            "9223372036854775808",
+            "integer literal is too large for a 64-bit integer",
+        );
+        test_parser::<Literal, &str>("18446744073709551616", "integer literal is too large");
+        test_parser::<Literal, &str>("0x8000000000000001", "integer literal is too large");
+        test_parser::<Literal, &str>(
+            "170141183460469231731687303715884105727",
+            "integer literal is too large",
+        );
     }
 
     #[test]
@@ -234,10 +494,102 @@ mod tests {
         test_parser(r#""""#, Literal::String(Arc::new("".to_string())));
     }
 
+    #[test]
+    fn test_parse_escape_sequences() {
+        test_parser(r#""a\"b""#, Literal::String(Arc::new("a\"b".to_string())));
+        test_parser(
+            r#""\a\b\f\n\r\t\v\\""#,
+            Literal::String(Arc::new("\x07\x08\x0C\n\r\t\x0B\\".to_string())),
+        );
+        test_parser(r#""\x41\x7a""#, Literal::String(Arc::new("Az".to_string())));
+        test_parser(
+            r#""\101\000""#,
+            Literal::String(Arc::new("A\0".to_string())),
+        );
+        test_parser(r#""é""#, Literal::String(Arc::new("é".to_string())));
+        test_parser(
+            r#""\U0001F600""#,
+            Literal::String(Arc::new("😀".to_string())),
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_escape_sequences() {
+        test_parser::<Literal, &str>(r#""\q""#, "invalid escape sequence: \\q");
+        test_parser::<Literal, &str>(r#""\400""#, "invalid octal byte value: \\400");
+        test_parser::<Literal, &str>(r#""\uD800""#, "invalid Unicode code point: D800");
+        test_parser::<Literal, &str>(r#""\U00110000""#, "invalid Unicode code point: 00110000");
+        test_parser::<Literal, &str>(r#""\x4""#, "invalid escape sequence: \\x");
+        test_parser::<Literal, &str>(
+            r#""\xff""#,
+            "invalid byte value: \\xff is not ASCII, use \\u00FF for the character U+00FF",
+        );
+        test_parser::<Literal, &str>(r#""\377""#, "invalid byte value: \\377 is not ASCII");
+        test_parser(
+            r#""\x7f\177""#,
+            Literal::String(Arc::new("\x7f\x7f".to_string())),
+        );
+    }
+
+    #[test]
+    fn test_invalid_escape_sequence_spans() {
+        let errors = Literal::lexer().parse(r#""ok \q \uD800""#).into_errors();
+        let spans = errors
+            .iter()
+            .map(|e| e.span().into_range())
+            .collect::<Vec<_>>();
+        assert_eq!(spans, vec![4..6, 7..13]);
+    }
+
+    #[test]
+    fn test_parse_raw_string() {
+        test_parser(r#"`a\d+`"#, Literal::String(Arc::new(r"a\d+".to_string())));
+        test_parser(
+            "`{\"k\": \"v\"}\n// not a comment`",
+            Literal::String(Arc::new("{\"k\": \"v\"}\n// not a comment".to_string())),
+        );
+        test_parser("``", Literal::String(Arc::new("".to_string())));
+        test_parser::<Literal, &str>("`open", "found end of input");
+    }
+
+    #[test]
+    fn test_parse_heredoc() {
+        test_parser(
+            "<<EOF\n    {\n      \"a\": \"\\d\"\n\n    }\n    EOF",
+            Literal::String(Arc::new("{\n  \"a\": \"\\d\"\n\n}\n".to_string())),
+        );
+        test_parser(
+            "<<END  \r\nline\r\nEND",
+            Literal::String(Arc::new("line\n".to_string())),
+        );
+        test_parser("<<EOF\nEOF", Literal::String(Arc::new("".to_string())));
+        // Mixed indentation only loses the prefix common to every line
+        test_parser(
+            "<<EOF\n  a\n\tb\nEOF",
+            Literal::String(Arc::new("  a\n\tb\n".to_string())),
+        );
+        test_parser(
+            "<<EOF\n\t  a\n\t b\n\nEOF",
+            Literal::String(Arc::new(" a\nb\n\n".to_string())),
+        );
+        test_parser::<Literal, &str>(
+            "<<EOF\ntext\nEO",
+            "unterminated heredoc: expected a line containing only `EOF`",
+        );
+    }
+
     #[test]
     fn test_parse_boolean() {
         test_parser("true", Literal::Boolean(true));
         test_parser("false", Literal::Boolean(false));
+        test_parser::<Literal, &str>("trueish", "found trueish expected");
+    }
+
+    #[test]
+    fn test_parse_null_and_undefined() {
+        test_parser("null", Literal::Null);
+        test_parser("undefined", Literal::Undefined);
+        test_parser::<Literal, &str>("nullable", "found nullable expected");
     }
 
     impl From<Literal> for Expect<Literal> {
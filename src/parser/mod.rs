@@ -1,7 +1,7 @@
-use crate::parser::literal::Literal;
-use ariadne::{Color, Label, Report, ReportKind, Source};
-use chumsky::error::Rich;
-use chumsky::prelude::just;
+use crate::diagnostic::{Diagnostic, Format, Sources};
+use chumsky::error::{Rich, RichReason};
+use chumsky::input::{Input, SpannedInput};
+use chumsky::prelude::empty;
 use chumsky::{extra, Parser};
 use std::fmt::Debug;
 
@@ -9,36 +9,89 @@ mod binary_operator;
 mod comment;
 mod expression;
 mod identifier;
+mod import;
 mod literal;
+mod param;
+mod policy;
 mod quantifier;
+mod span;
 mod statement;
+mod token;
 mod unary_operator;
 
-pub(crate) use binary_operator::*;
-pub(crate) use expression::*;
-pub(crate) use identifier::*;
-pub(crate) use literal::*;
-pub(crate) use quantifier::*;
-pub(crate) use statement::*;
-pub(crate) use unary_operator::*;
+pub use binary_operator::*;
+pub(crate) use comment::*;
+pub use expression::*;
+pub use identifier::*;
+pub use import::*;
+pub use literal::*;
+pub use param::*;
+pub use policy::*;
+pub use quantifier::*;
+pub use span::*;
+pub use statement::*;
+pub(crate) use token::*;
+pub use unary_operator::*;
 
-type ParsableError<'src> = extra::Err<Rich<'src, char>>;
+type LexError<'src> = extra::Err<Rich<'src, char>>;
+type ParsableError<'src> = extra::Err<Rich<'src, Token<'src>, Span>>;
+type ParserInput<'src> = SpannedInput<Token<'src>, Span, &'src [(Token<'src>, Span)]>;
+
+/// An error from either the lexer or the grammar, with the offending token rendered
+/// as text so both kinds can be reported together
+pub(crate) type SyntaxError = Rich<'static, String, Span>;
 
 pub(crate) trait Parsable {
-    fn parser<'src>() -> impl Parser<'src, &'src str, Self, ParsableError<'src>>
+    fn parser<'src>() -> impl Parser<'src, ParserInput<'src>, Self, ParsableError<'src>>
     where
         Self: Sized;
 }
 
 impl Parsable for () {
-    fn parser<'src>() -> impl Parser<'src, &'src str, Self, ParsableError<'src>>
+    fn parser<'src>() -> impl Parser<'src, ParserInput<'src>, Self, ParsableError<'src>>
     where
         Self: Sized,
     {
-        just("").to(())
+        empty().to(())
     }
 }
 
+/// Lexes `source` and parses the tokens as a `T`, with every span pointing into `file`.
+/// The grammar runs even if lexing reported errors, so the returned errors cover both
+/// stages.
+pub(crate) fn parse<T: Parsable>(source: &str, file: FileId) -> (Option<T>, Vec<SyntaxError>) {
+    let (tokens, lex_errors) = lexer().parse(source).into_output_errors();
+    let mut errors = lex_errors
+        .into_iter()
+        .map(|e| Rich::custom(Span::new(file, e.span().into_range()), e.reason()))
+        .collect::<Vec<_>>();
+    let output = tokens.and_then(|tokens| {
+        let tokens = tokens
+            .into_iter()
+            .map(|(token, span)| (token, Span::new(file, span.into_range())))
+            .collect::<Vec<_>>();
+        let eoi = Span::new(file, source.len()..source.len());
+        let (output, parse_errors) = T::parser()
+            .parse(tokens.as_slice().spanned(eoi))
+            .into_output_errors();
+        // chumsky spans an error at the end of the input from the first token, so it is
+        // moved to where the input ran out
+        errors.extend(parse_errors.into_iter().map(|e| {
+            let e = e.map_token(|t| t.to_string()).into_owned();
+            match e.reason() {
+                RichReason::ExpectedFound { found: None, .. } => Rich::custom(eoi, e.reason()),
+                _ => e,
+            }
+        }));
+        output
+    });
+    // Lexer and grammar errors are reported in source order. Nested recovery strategies
+    // can report the same failure more than once.
+    errors.sort_by_key(|e| (e.span().start, e.span().end));
+    errors.dedup_by(|a, b| a.span() == b.span() && a.reason() == b.reason());
+    (output, errors)
+}
+
 pub(crate) enum Expect<T> {
     Something(T),
     Error(String),
@@ -50,27 +103,34 @@ impl<T> From<&str> for Expect<T> {
     }
 }
 
+/// Prints each error against the input and returns their reasons
+fn report(input: &str, errors: &[SyntaxError]) -> Vec<String> {
+    let mut sources = Sources::new();
+    sources.add("<input>", input);
+    errors
+        .iter()
+        .map(|e| {
+            let diagnostic = Diagnostic::from(e.clone());
+            println!("{}", diagnostic.render(&sources, Format::Colored));
+            diagnostic.message
+        })
+        .collect()
+}
+
 pub(crate) fn test_parser<K: Parsable + Debug + PartialEq, T: Into<Expect<K>>>(
     input: &str,
     expected: T,
 ) {
-    let (out, errors) = K::parser().parse(input).into_output_errors();
-    let mut reasons = vec![];
-    errors.iter().for_each(|e| {
-        reasons.push(e.reason().to_string());
-        Report::build(ReportKind::Error, (), e.span().start)
-            .with_message(e.to_string())
-            .with_label(
-                Label::new(e.span().into_range())
-                    .with_message(e.reason().to_string())
-                    .with_color(Color::Red),
-            )
-            .finish()
-            .print(Source::from(input))
-            .unwrap()
-    });
+    let (out, errors) = parse::<K>(input, FileId::default());
+    let reasons = report(input, &errors);
     match expected.into() {
         Expect::Something(expected) => {
+            assert!(
+                reasons.is_empty(),
+                "input: {:?} errors: {:?}",
+                input,
+                reasons
+            );
             assert_eq!(out.unwrap(), expected, "input: {:?}", input);
         }
         Expect::Error(expected) => {
@@ -80,3 +140,45 @@ pub(crate) fn test_parser<K: Parsable + Debug + PartialEq, T: Into<Expect<K>>>(
         }
     }
 }
+
+/// An identifier expression, for building expected trees in tests
+pub(crate) fn ident(name: &str) -> Expression {
+    Expression::Identifier(Identifier::new(name))
+}
+
+/// An integer literal expression, for building expected trees in tests
+pub(crate) fn int(value: i64) -> Expression {
+    Expression::Literal(Literal::Integer(value))
+}
+
+/// Parses input containing syntax errors, checking the tree recovered from it and that
+/// exactly the expected errors were reported, in order
+pub(crate) fn test_recovery<K: Parsable + Debug + PartialEq>(
+    input: &str,
+    expected: K,
+    expected_errors: &[&str],
+) {
+    let (out, errors) = parse::<K>(input, FileId::default());
+    let reasons = report(input, &errors);
+    assert_eq!(
+        reasons.len(),
+        expected_errors.len(),
+        "input: {:?} errors: {:?}",
+        input,
+        reasons
+    );
+    for (reason, expected) in reasons.iter().zip(expected_errors) {
+        assert!(
+            reason.contains(expected),
+            "expected error: {:?} got: {:?}",
+            expected,
+            reason
+        );
+    }
+    assert_eq!(
+        out.expect("a partial tree should be recovered"),
+        expected,
+        "input: {:?}",
+        input
+    );
+}
@@ -0,0 +1,70 @@
+use crate::parser::{
+    keyword, Expression, Identifier, Parsable, ParsableError, ParserInput, Spanned,
+};
+use chumsky::prelude::*;
+
+/// A policy parameter declaration, e.g. `param max_instances default 10`. Parameters
+/// without a default value must be supplied by the host when the policy is evaluated.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Param {
+    pub name: Spanned<Identifier>,
+    pub default: Option<Spanned<Expression>>,
+}
+
+impl Param {
+    pub fn new(name: Identifier, default: Option<Expression>) -> Self {
+        Param {
+            name: name.into(),
+            default: default.map(Spanned::from),
+        }
+    }
+}
+
+impl Parsable for Param {
+    fn parser<'src>() -> impl Parser<'src, ParserInput<'src>, Self, ParsableError<'src>> {
+        keyword("param")
+            .ignore_then(Identifier::name())
+            .then(
+                keyword("default")
+                    .ignore_then(Spanned::<Expression>::parser())
+                    .or_not(),
+            )
+            .map(|(name, default)| Param { name, default })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{test_parser, Expect, Literal};
+
+    #[test]
+    fn test_param() {
+        test_parser(
+            "param regions",
+            Param::new(Identifier::new("regions"), None),
+        );
+        test_parser(
+            "param max_instances default 10",
+            Param::new(
+                Identifier::new("max_instances"),
+                Some(Expression::Literal(Literal::Integer(10))),
+            ),
+        );
+        test_parser(
+            "param regions default [\n    \"us-east-1\",\n]",
+            Param::new(
+                Identifier::new("regions"),
+                Some(Expression::list(vec![Expression::Literal(
+                    Literal::String("us-east-1".to_string().into()),
+                )])),
+            ),
+        );
+    }
+
+    impl From<Param> for Expect<Param> {
+        fn from(value: Param) -> Self {
+            Expect::Something(value)
+        }
+    }
+}
@@ -0,0 +1,655 @@
+use crate::diagnostic::Diagnostics;
+use crate::parser::{
+    parse, terminator, Expression, FileId, Identifier, Import, Param, Parsable, ParsableError,
+    ParserInput, Spanned, Statement,
+};
+use chumsky::prelude::*;
+use std::collections::HashMap;
+
+/// The root of a parsed policy file. Top-level assignments of rule expressions, such as
+/// `main = rule { ... }`, become rules rather than statements.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Policy {
+    pub imports: Vec<Spanned<Import>>,
+    pub params: Vec<Spanned<Param>>,
+    /// The statements and rules of the policy, in the order they run
+    pub items: Vec<Item>,
+}
+
+/// A statement or rule at the top level of a policy
+#[derive(Debug, PartialEq, Clone)]
+pub enum Item {
+    Statement(Spanned<Statement>),
+    Rule(Spanned<Rule>),
+}
+
+impl Policy {
+    /// The top-level statements, in order, without the rules between them
+    pub fn statements(&self) -> impl Iterator<Item = &Spanned<Statement>> {
+        self.items.iter().filter_map(|item| match item {
+            Item::Statement(statement) => Some(statement),
+            Item::Rule(_) => None,
+        })
+    }
+
+    /// The rules, in the order they were declared
+    pub fn rules(&self) -> impl Iterator<Item = &Spanned<Rule>> {
+        self.items.iter().filter_map(|item| match item {
+            Item::Rule(rule) => Some(rule),
+            Item::Statement(_) => None,
+        })
+    }
+}
+
+/// A named rule declared at the top level of a policy
+#[derive(Debug, PartialEq, Clone)]
+pub struct Rule {
+    pub name: Spanned<Identifier>,
+    pub when: Option<Spanned<Expression>>,
+    pub body: Spanned<Expression>,
+}
+
+impl Rule {
+    pub fn new(name: Identifier, when: Option<Expression>, body: Expression) -> Self {
+        Rule {
+            name: name.into(),
+            when: when.map(Spanned::from),
+            body: body.into(),
+        }
+    }
+}
+
+/// Parses a policy from source, returning every syntax error found if it is invalid
+pub fn parse_policy(source: &str) -> Result<Policy, Diagnostics> {
+    parse_policy_file(FileId::default(), source)
+}
+
+/// Parses a policy from the source of `file`, so that every span in the tree points
+/// into that file
+pub fn parse_policy_file(file: FileId, source: &str) -> Result<Policy, Diagnostics> {
+    match parse_policy_partial(file, source) {
+        (Some(policy), diagnostics) if diagnostics.is_empty() => Ok(policy),
+        (_, diagnostics) => Err(diagnostics),
+    }
+}
+
+/// Parses a policy, recovering from syntax errors so that every error in the file is
+/// reported. Parts of the tree that failed to parse are replaced by `Error` nodes, which
+/// leaves the rest usable by tools such as editors.
+pub fn parse_policy_partial(file: FileId, source: &str) -> (Option<Policy>, Diagnostics) {
+    let (policy, errors) = parse::<Policy>(source, file);
+    (policy, errors.into())
+}
+
+/// A top-level line following the imports of a policy
+#[derive(Clone)]
+enum Entry {
+    Param(Spanned<Param>),
+    Statement(Spanned<Statement>),
+    MisplacedImport,
+}
+
+impl Parsable for Policy {
+    fn parser<'src>() -> impl Parser<'src, ParserInput<'src>, Self, ParsableError<'src>> {
+        // Imports are only allowed at the top of the file.
+        // Each import, parameter and statement must be on its own line
+        let imports = Import::parser()
+            .map_with(|import, e| Spanned::new(import, e.span()))
+            .then_ignore(terminator())
+            .repeated()
+            .collect::<Vec<_>>();
+
+        let misplaced_import = Import::parser().validate(|_, e, emitter| {
+            emitter.emit(Rich::custom(
+                e.span(),
+                "imports must appear at the top of the policy, before any other statement",
+            ))
+        });
+
+        let param =
+            Param::parser().map_with(|param, e| Entry::Param(Spanned::new(param, e.span())));
+
+        let entries = choice((
+            misplaced_import.to(Entry::MisplacedImport),
+            param,
+            Spanned::<Statement>::parser().map(Entry::Statement),
+        ))
+        .then_ignore(terminator())
+        // Statements recover on their own, so this only skips tokens such as a stray
+        // closing brace that cannot start any item
+        .recover_with(skip_then_retry_until(any().ignored(), end()))
+        .repeated()
+        .collect::<Vec<_>>()
+        .validate(|entries, _, emitter| {
+            let mut params = vec![];
+            let mut items = vec![];
+            for entry in entries {
+                match entry {
+                    Entry::Param(param) => params.push(param),
+                    Entry::Statement(statement) => {
+                        if statement.contains_return() {
+                            emitter.emit(Rich::custom(
+                                statement.span,
+                                "`return` is only allowed inside a function body",
+                            ));
+                        }
+                        if let Some(span) = statement.loop_control() {
+                            emitter.emit(Rich::custom(
+                                span,
+                                "`break` and `continue` are only allowed inside a `for` loop",
+                            ));
+                        }
+                        match statement.node {
+                            Statement::Assignment {
+                                target:
+                                    Spanned {
+                                        node: Expression::Identifier(name),
+                                        span: name_span,
+                                    },
+                                op: None,
+                                value:
+                                    Spanned {
+                                        node: Expression::Rule { when, body },
+                                        ..
+                                    },
+                            } => items.push(Item::Rule(Spanned::new(
+                                Rule {
+                                    name: Spanned::new(name, name_span),
+                                    when: when.map(|w| *w),
+                                    body: *body,
+                                },
+                                statement.span,
+                            ))),
+                            node => items.push(Item::Statement(Spanned::new(node, statement.span))),
+                        }
+                    }
+                    Entry::MisplacedImport => {}
+                }
+            }
+            (params, items)
+        });
+
+        // Imports and parameters share the global namespace, so each must be bound to a
+        // unique name
+        imports
+            .then(entries)
+            .validate(|(imports, (params, items)), _, emitter| {
+                let mut seen = HashMap::new();
+                for import in &imports {
+                    if seen.insert(import.binding(), "import").is_some() {
+                        emitter.emit(Rich::custom(
+                            import.span,
+                            format!("duplicate import alias `{}`", import.binding()),
+                        ));
+                    }
+                }
+                for param in &params {
+                    match seen.insert(&param.name.0, "param") {
+                        Some("import") => emitter.emit(Rich::custom(
+                            param.span,
+                            format!(
+                                "duplicate name `{}`, which is already bound by an import",
+                                param.name.0
+                            ),
+                        )),
+                        Some(_) => emitter.emit(Rich::custom(
+                            param.span,
+                            format!("duplicate parameter `{}`", param.name.0),
+                        )),
+                        None => {}
+                    }
+                }
+                Policy {
+                    imports,
+                    params,
+                    items,
+                }
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{test_parser, test_recovery, BinaryOperator, Expect, Literal};
+
+    impl From<Statement> for Item {
+        fn from(value: Statement) -> Self {
+            Item::Statement(value.into())
+        }
+    }
+
+    impl From<Rule> for Item {
+        fn from(value: Rule) -> Self {
+            Item::Rule(value.into())
+        }
+    }
+
+    #[test]
+    fn test_empty_policy() {
+        test_parser("", Policy::default());
+        test_parser("  // nothing to see here\n", Policy::default());
+    }
+
+    #[test]
+    fn test_policy() {
+        test_parser(
+            "// imports\nimport \"tfplan/v2\" as tfplan\nimport \"strings\"\n\nmain = rule { true }\n",
+            Policy {
+                imports: vec![
+                    Import::new("tfplan/v2", Some(Identifier::new("tfplan"))).into(),
+                    Import::new("strings", None).into(),
+                ],
+                params: vec![],
+                items: vec![Rule::new(
+                    Identifier::new("main"),
+                    None,
+                    Expression::Literal(Literal::Boolean(true)),
+                )
+                .into()],
+            },
+        );
+    }
+
+    #[test]
+    fn test_params() {
+        test_parser(
+            "import \"strings\"\nparam regions\nx = 1\nparam limit default 10\n",
+            Policy {
+                imports: vec![Import::new("strings", None).into()],
+                params: vec![
+                    Param::new(Identifier::new("regions"), None).into(),
+                    Param::new(
+                        Identifier::new("limit"),
+                        Some(Expression::Literal(Literal::Integer(10))),
+                    )
+                    .into(),
+                ],
+                items: vec![Statement::assignment(
+                    Expression::Identifier(Identifier::new("x")),
+                    Expression::Literal(Literal::Integer(1)),
+                )
+                .into()],
+            },
+        );
+        test_parser::<Policy, &str>(
+            "param limit\nparam limit default 1",
+            "duplicate parameter `limit`",
+        );
+        test_parser::<Policy, &str>(
+            "import \"a\" as b\nparam b",
+            "duplicate name `b`, which is already bound by an import",
+        );
+        test_parser::<Policy, &str>(
+            "import \"tfplan/v2\"\nparam v2 default 1",
+            "duplicate name `v2`",
+        );
+    }
+
+    #[test]
+    fn test_break_outside_loop() {
+        test_parser::<Policy, &str>(
+            "break",
+            "`break` and `continue` are only allowed inside a `for` loop",
+        );
+        test_parser::<Policy, &str>(
+            "if x {\n  continue\n}",
+            "`break` and `continue` are only allowed inside a `for` loop",
+        );
+        test_parser::<Policy, &str>(
+            "f = func() {\n  break\n}",
+            "`break` and `continue` are only allowed inside a `for` loop",
+        );
+        test_parser(
+            "for xs as x {\n  if x { continue }\n}",
+            Policy {
+                imports: vec![],
+                params: vec![],
+                items: vec![Statement::For {
+                    collection: Expression::Identifier(Identifier::new("xs")).into(),
+                    key: None,
+                    value: Identifier::new("x").into(),
+                    body: Box::new(
+                        Statement::block(vec![Statement::If {
+                            condition: Expression::Identifier(Identifier::new("x")).into(),
+                            then_branch: Box::new(
+                                Statement::block(vec![Statement::Continue]).into(),
+                            ),
+                            else_branch: None,
+                        }])
+                        .into(),
+                    ),
+                }
+                .into()],
+            },
+        );
+    }
+
+    #[test]
+    fn test_items_on_one_line() {
+        test_parser::<Policy, &str>(
+            "import \"a\" import \"b\"",
+            "expected a line break before the next statement",
+        );
+        test_parser::<Policy, &str>(
+            "param a param b",
+            "expected a line break before the next statement",
+        );
+        test_parser::<Policy, &str>(
+            "x = 1 y = 2",
+            "expected a line break before the next statement",
+        );
+    }
+
+    #[test]
+    fn test_return_outside_function() {
+        test_parser::<Policy, &str>(
+            "if x {\n  return 1\n}",
+            "`return` is only allowed inside a function body",
+        );
+        test_parser(
+            "f = func() {\n  if x { return 1 }\n}",
+            Policy {
+                imports: vec![],
+                params: vec![],
+                items: vec![Statement::assignment(
+                    Expression::Identifier(Identifier::new("f")),
+                    Expression::function(
+                        vec![],
+                        Statement::block(vec![Statement::If {
+                            condition: Expression::Identifier(Identifier::new("x")).into(),
+                            then_branch: Box::new(
+                                Statement::block(vec![Statement::Return(Some(
+                                    Expression::Literal(Literal::Integer(1)).into(),
+                                ))])
+                                .into(),
+                            ),
+                            else_branch: None,
+                        }]),
+                    ),
+                )
+                .into()],
+            },
+        );
+    }
+
+    #[test]
+    fn test_return_field_at_end_of_line() {
+        test_parser(
+            "a = r.return\nb = 1",
+            Policy {
+                imports: vec![],
+                params: vec![],
+                items: vec![
+                    Statement::assignment(
+                        Expression::Identifier(Identifier::new("a")),
+                        Expression::select(
+                            Expression::Identifier(Identifier::new("r")),
+                            Identifier::new("return"),
+                        ),
+                    )
+                    .into(),
+                    Statement::assignment(
+                        Expression::Identifier(Identifier::new("b")),
+                        Expression::Literal(Literal::Integer(1)),
+                    )
+                    .into(),
+                ],
+            },
+        );
+    }
+
+    #[test]
+    fn test_rules() {
+        test_parser(
+            "limit = 3\nis_small = rule when limit > 0 { limit < 5 }\nmain = rule { is_small }",
+            Policy {
+                imports: vec![],
+                params: vec![],
+                items: vec![
+                    Statement::assignment(
+                        Expression::Identifier(Identifier::new("limit")),
+                        Expression::Literal(Literal::Integer(3)),
+                    )
+                    .into(),
+                    Rule::new(
+                        Identifier::new("is_small"),
+                        Some(Expression::binary_expr(
+                            Expression::Identifier(Identifier::new("limit")),
+                            BinaryOperator::GreaterThan,
+                            Expression::Literal(Literal::Integer(0)),
+                        )),
+                        Expression::binary_expr(
+                            Expression::Identifier(Identifier::new("limit")),
+                            BinaryOperator::LessThan,
+                            Expression::Literal(Literal::Integer(5)),
+                        ),
+                    )
+                    .into(),
+                    Rule::new(
+                        Identifier::new("main"),
+                        None,
+                        Expression::Identifier(Identifier::new("is_small")),
+                    )
+                    .into(),
+                ],
+            },
+        );
+    }
+
+    #[test]
+    fn test_parse_policy() {
+        let policy = parse_policy("import \"strings\"\nmain = rule { true }").unwrap();
+        assert_eq!(policy.imports, vec![Import::new("strings", None).into()]);
+        assert_eq!(policy.rules().count(), 1);
+
+        let diagnostics = parse_policy("main = rule {").unwrap_err();
+        assert!(!diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_misplaced_import() {
+        test_parser::<Policy, &str>(
+            "x = 1\nimport \"strings\"",
+            "imports must appear at the top of the policy",
+        );
+    }
+
+    #[test]
+    fn test_duplicate_import_alias() {
+        test_parser::<Policy, &str>(
+            "import \"strings\"\nimport \"tfplan/v2\" as strings",
+            "duplicate import alias `strings`",
+        );
+        test_parser::<Policy, &str>(
+            "import \"tfplan/v2\" as plan\nimport \"tfplan\" as plan",
+            "duplicate import alias `plan`",
+        );
+        test_parser::<Policy, &str>(
+            "import \"tfplan/v2\"\nimport \"tfstate/v2\"",
+            "duplicate import alias `v2`",
+        );
+    }
+
+    #[test]
+    fn test_reserved_keyword_as_name() {
+        test_parser::<Policy, &str>(
+            "default = 1",
+            "`default` is a reserved keyword and cannot be used as a name",
+        );
+        test_parser::<Policy, &str>("param if", "`if` is a reserved keyword");
+    }
+
+    #[test]
+    fn test_recover_every_error() {
+        test_recovery(
+            "x = (1 + )\ny = 2\nz = [1, , 3]\nmain = rule { y }",
+            Policy {
+                imports: vec![],
+                params: vec![],
+                items: vec![
+                    Statement::assignment(
+                        Expression::Identifier(Identifier::new("x")),
+                        Expression::Error,
+                    )
+                    .into(),
+                    Statement::assignment(
+                        Expression::Identifier(Identifier::new("y")),
+                        Expression::Literal(Literal::Integer(2)),
+                    )
+                    .into(),
+                    Statement::assignment(
+                        Expression::Identifier(Identifier::new("z")),
+                        Expression::Error,
+                    )
+                    .into(),
+                    Rule::new(
+                        Identifier::new("main"),
+                        None,
+                        Expression::Identifier(Identifier::new("y")),
+                    )
+                    .into(),
+                ],
+            },
+            &["found ) expected", "found , expected"],
+        );
+        test_recovery(
+            "x = 1\n}\ny = 2",
+            Policy {
+                imports: vec![],
+                params: vec![],
+                items: vec![
+                    Statement::assignment(
+                        Expression::Identifier(Identifier::new("x")),
+                        Expression::Literal(Literal::Integer(1)),
+                    )
+                    .into(),
+                    Statement::assignment(
+                        Expression::Identifier(Identifier::new("y")),
+                        Expression::Literal(Literal::Integer(2)),
+                    )
+                    .into(),
+                ],
+            },
+            &["found } expected"],
+        );
+    }
+
+    #[test]
+    fn test_recover_unfinished_statements() {
+        let policy = |items: Vec<Item>| Policy {
+            imports: vec![],
+            params: vec![],
+            items,
+        };
+        // Each error is reported once, where the statement stops making sense
+        let (_, diagnostics) = parse_policy_partial(FileId(0), "x = [1, 2");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics.0[0].span().start..diagnostics.0[0].span().end,
+            9..9
+        );
+        // A group that is never closed runs to the end of the input
+        test_recovery(
+            "x = [1, 2",
+            policy(vec![Statement::assignment(
+                Expression::Identifier(Identifier::new("x")),
+                Expression::Error,
+            )
+            .into()]),
+            &["found end of input expected"],
+        );
+        test_recovery(
+            "main = rule { a ",
+            policy(vec![Rule::new(
+                Identifier::new("main"),
+                None,
+                Expression::Error,
+            )
+            .into()]),
+            &["found end of input expected"],
+        );
+        test_recovery(
+            "main = rule {\n  x ==\n}",
+            policy(vec![Rule::new(
+                Identifier::new("main"),
+                None,
+                Expression::Error,
+            )
+            .into()]),
+            &["found } expected '+', '-', '!', 'not', or an expression"],
+        );
+    }
+
+    #[test]
+    fn test_map_recovery() {
+        let assignments = |name: &str| Policy {
+            imports: vec![],
+            params: vec![],
+            items: vec![
+                Statement::assignment(
+                    Expression::Identifier(Identifier::new(name)),
+                    Expression::Error,
+                )
+                .into(),
+                Statement::assignment(
+                    Expression::Identifier(Identifier::new("y")),
+                    Expression::Literal(Literal::Integer(2)),
+                )
+                .into(),
+            ],
+        };
+        test_recovery(
+            "x = {\"a\": , \"b\": 1}\ny = 2",
+            assignments("x"),
+            &["found , expected"],
+        );
+        // A reserved word is reported as a misused name, leaving the rest of the map
+        let mut reserved_key = assignments("x");
+        reserved_key.items[0] = Statement::assignment(
+            Expression::Identifier(Identifier::new("x")),
+            Expression::map(vec![(
+                Expression::Error,
+                Expression::Literal(Literal::Integer(1)),
+            )]),
+        )
+        .into();
+        test_recovery(
+            "x = {default: 1}\ny = 2",
+            reserved_key,
+            &["`default` is a reserved keyword and cannot be used as a name"],
+        );
+        // Lexer errors are reported in source order along with grammar errors
+        test_recovery(
+            "x = {\"a\": , \"b\": 1}\ny = 2 @",
+            assignments("x"),
+            &["found , expected", "unexpected character `@`"],
+        );
+        let (policy, diagnostics) = parse_policy_partial(FileId(0), "x = {default: 1}");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(policy.unwrap().statements().count(), 1);
+    }
+
+    #[test]
+    fn test_parse_policy_partial() {
+        let source = "x = [1,,]\nmain = rule { true }";
+        let (policy, diagnostics) = parse_policy_partial(FileId(1), source);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(policy.unwrap().rules().count(), 1);
+        assert_eq!(
+            parse_policy_file(FileId(1), source).unwrap_err(),
+            diagnostics
+        );
+    }
+
+    #[test]
+    fn test_unterminated_trailing_comment() {
+        test_parser::<Policy, &str>("x = 1 /* oops", "unterminated block comment");
+    }
+
+    impl From<Policy> for Expect<Policy> {
+        fn from(value: Policy) -> Self {
+            Expect::Something(value)
+        }
+    }
+}
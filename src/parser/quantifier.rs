@@ -1,4 +1,4 @@
-use crate::parser::{Parsable, ParsableError};
+use crate::parser::{keyword, Parsable, ParsableError, ParserInput};
 use chumsky::prelude::*;
 
 #[derive(Debug, PartialEq, Clone)]
@@ -10,12 +10,12 @@ pub enum QuantifierType {
 }
 
 impl Parsable for QuantifierType {
-    fn parser<'src>() -> impl Parser<'src, &'src str, Self, ParsableError<'src>> {
+    fn parser<'src>() -> impl Parser<'src, ParserInput<'src>, Self, ParsableError<'src>> {
         choice((
-            text::keyword("all").to(QuantifierType::All),
-            text::keyword("any").to(QuantifierType::Any),
-            text::keyword("filter").to(QuantifierType::Filter),
-            text::keyword("map").to(QuantifierType::Map),
+            keyword("all").to(QuantifierType::All),
+            keyword("any").to(QuantifierType::Any),
+            keyword("filter").to(QuantifierType::Filter),
+            keyword("map").to(QuantifierType::Map),
         ))
     }
 }
@@ -0,0 +1,181 @@
+use std::fmt::{Display, Formatter};
+use std::ops::{Deref, Range};
+
+/// Identifies the source file a span points into
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct FileId(pub usize);
+
+/// A byte range within a source file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Span {
+    pub file: FileId,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(file: FileId, range: Range<usize>) -> Self {
+        Span {
+            file,
+            start: range.start,
+            end: range.end,
+        }
+    }
+
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+
+    /// The smallest span covering both this span and `other`
+    pub fn union(self, other: Span) -> Span {
+        Span {
+            file: self.file,
+            start: self.start.min(other.start),
+            end: self.end.max(other.end),
+        }
+    }
+}
+
+impl Display for Span {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}..{}", self.start, self.end)
+    }
+}
+
+impl chumsky::span::Span for Span {
+    type Context = FileId;
+    type Offset = usize;
+
+    fn new(context: FileId, range: Range<usize>) -> Self {
+        Span::new(context, range)
+    }
+
+    fn context(&self) -> FileId {
+        self.file
+    }
+
+    fn start(&self) -> usize {
+        self.start
+    }
+
+    fn end(&self) -> usize {
+        self.end
+    }
+}
+
+/// A syntax node along with the source it was parsed from. Spans are ignored when
+/// comparing nodes, so a parsed tree equals one built by hand.
+#[derive(Debug, Clone)]
+pub struct Spanned<T> {
+    pub node: T,
+    pub span: Span,
+}
+
+impl<T> Spanned<T> {
+    pub fn new(node: T, span: Span) -> Self {
+        Spanned { node, span }
+    }
+
+    /// Transforms the node, keeping its span
+    pub fn map<U, F: FnOnce(T) -> U>(self, f: F) -> Spanned<U> {
+        Spanned::new(f(self.node), self.span)
+    }
+}
+
+/// Wraps a node that was not parsed from any source, giving it an empty span
+impl<T> From<T> for Spanned<T> {
+    fn from(node: T) -> Self {
+        Spanned::new(node, Span::default())
+    }
+}
+
+impl<T> Deref for Spanned<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.node
+    }
+}
+
+impl<T: PartialEq> PartialEq for Spanned<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.node == other.node
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{parse, Expression, Identifier, Statement, StringPart};
+
+    #[test]
+    fn test_equality_ignores_spans() {
+        let a = Spanned::new(1, Span::new(FileId(0), 0..1));
+        let b = Spanned::new(1, Span::new(FileId(1), 5..9));
+        assert_eq!(a, b);
+        assert_ne!(a, Spanned::from(2));
+    }
+
+    #[test]
+    fn test_union() {
+        let a = Span::new(FileId(0), 4..6);
+        assert_eq!(a.union(Span::new(FileId(0), 1..3)).range(), 1..6);
+    }
+
+    #[test]
+    fn test_expression_spans() {
+        let (expr, _) = parse::<Spanned<Expression>>("a + f(b)[0]", FileId(3));
+        let expr = expr.unwrap();
+        assert_eq!(expr.span, Span::new(FileId(3), 0..11));
+        let Expression::BinaryExpr { left, right, .. } = &expr.node else {
+            panic!("expected a binary expression, got {:?}", expr.node)
+        };
+        assert_eq!(left.span.range(), 0..1);
+        assert_eq!(right.span.range(), 4..11);
+        let Expression::Index { collection, index } = &right.node else {
+            panic!("expected an index expression, got {:?}", right.node)
+        };
+        assert_eq!(collection.span.range(), 4..8);
+        assert_eq!(index.span.range(), 9..10);
+    }
+
+    #[test]
+    fn test_interpolation_spans() {
+        let source = r#""id ${r.id} in ${ {"a": 1}["a"] }""#;
+        let (expr, errors) = parse::<Spanned<Expression>>(source, FileId::default());
+        assert!(errors.is_empty(), "{:?}", errors);
+        let expr = expr.unwrap();
+        assert_eq!(expr.span.range(), 0..source.len());
+        let Expression::Interpolation(parts) = &expr.node else {
+            panic!("expected an interpolated string, got {:?}", expr.node)
+        };
+        let embedded = parts
+            .iter()
+            .filter_map(|part| match part {
+                StringPart::Expression(expr) => Some(&source[expr.span.range()]),
+                StringPart::Text(_) => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(embedded, vec!["r.id", r#"{"a": 1}["a"]"#]);
+    }
+
+    #[test]
+    fn test_statement_spans() {
+        let source = "if x {\n  y = -1\n}";
+        let (statement, _) = parse::<Spanned<Statement>>(source, FileId::default());
+        let statement = statement.unwrap();
+        assert_eq!(statement.span.range(), 0..source.len());
+        let Statement::If { then_branch, .. } = &statement.node else {
+            panic!("expected an if statement, got {:?}", statement.node)
+        };
+        let Statement::Block(body) = &then_branch.node else {
+            panic!("expected a block, got {:?}", then_branch.node)
+        };
+        assert_eq!(&source[body[0].span.range()], "y = -1");
+        let Statement::Assignment { target, value, .. } = &body[0].node else {
+            panic!("expected an assignment, got {:?}", body[0].node)
+        };
+        assert_eq!(target.node, Expression::Identifier(Identifier::new("y")));
+        assert_eq!(&source[value.span.range()], "-1");
+    }
+}
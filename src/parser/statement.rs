@@ -1,29 +1,778 @@
-use crate::parser::{Expression, Identifier};
+use crate::parser::{
+    balanced, ctrl, keyword, op, open_brace, skip_statement, terminator, BinaryOperator,
+    Expression, Identifier, Parsable, ParsableError, ParserInput, Span, Spanned, Token,
+};
+use chumsky::prelude::*;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum Statement {
-    Expression(Expression),
+    Expression(Spanned<Expression>),
+    /// `target = value`, or a compound assignment such as `target += value` when `op`
+    /// is set
     Assignment {
-        target: Expression,
-        value: Expression,
+        target: Spanned<Expression>,
+        op: Option<BinaryOperator>,
+        value: Spanned<Expression>,
     },
+    Block(Vec<Spanned<Statement>>),
     If {
-        condition: Expression,
-        then_branch: Box<Statement>,
-        else_branch: Option<Box<Statement>>,
+        condition: Spanned<Expression>,
+        then_branch: Box<Spanned<Statement>>,
+        else_branch: Option<Box<Spanned<Statement>>>,
     },
     Case {
-        expr: Option<Expression>,
-        clauses: Vec<(Expression, Statement)>,
-        else_clause: Option<Box<Statement>>,
+        expr: Option<Spanned<Expression>>,
+        clauses: Vec<(Vec<Spanned<Expression>>, Spanned<Statement>)>,
+        else_clause: Option<Box<Spanned<Statement>>>,
     },
     For {
-        collection: Expression,
-        key: Option<Identifier>,
-        value: Identifier,
-        body: Box<Statement>,
+        collection: Spanned<Expression>,
+        key: Option<Spanned<Identifier>>,
+        value: Spanned<Identifier>,
+        body: Box<Spanned<Statement>>,
     },
     Break,
     Continue,
-    Return(Option<Expression>),
+    Return(Option<Spanned<Expression>>),
+    /// See [`Expression::Error`]
+    Error,
+}
+
+impl Parsable for Statement {
+    fn parser<'src>() -> impl Parser<'src, ParserInput<'src>, Self, ParsableError<'src>> {
+        Spanned::<Statement>::parser().map(|statement| statement.node)
+    }
+}
+
+impl Parsable for Spanned<Statement> {
+    fn parser<'src>() -> impl Parser<'src, ParserInput<'src>, Self, ParsableError<'src>> {
+        Statement::parser_with(Spanned::<Expression>::parser().boxed())
+    }
+}
+
+impl Statement {
+    /// Parses a block of any number of statements wrapped in braces, each on its own line
+    pub(crate) fn block_parser<'src>(
+        statement: impl Parser<'src, ParserInput<'src>, Spanned<Self>, ParsableError<'src>> + Clone,
+    ) -> impl Parser<'src, ParserInput<'src>, Spanned<Self>, ParsableError<'src>> + Clone {
+        statement
+            .then_ignore(terminator())
+            .repeated()
+            .collect::<Vec<_>>()
+            .delimited_by(open_brace(), ctrl('}'))
+            .map_with(|statements, e| Spanned::new(Statement::Block(statements), e.span()))
+    }
+
+    /// Builds the statement parser on top of an existing expression parser, which lets
+    /// function literals parse their bodies from within the expression grammar.
+    pub(crate) fn parser_with<'src>(
+        expr: impl Parser<'src, ParserInput<'src>, Spanned<Expression>, ParsableError<'src>>
+            + Clone
+            + 'src,
+    ) -> impl Parser<'src, ParserInput<'src>, Spanned<Self>, ParsableError<'src>> + Clone {
+        recursive(|statement| {
+            let block = Self::block_parser(statement.clone()).boxed();
+
+            // Skips the header and body of a statement that failed to parse
+            let header = any()
+                .and_is(choice((ctrl('{'), just(Token::Newline).ignored())).not())
+                .repeated();
+            let clause = header
+                .then(just(Token::Newline).or_not())
+                .then(balanced('{', '}', |_| ()))
+                .ignored()
+                .boxed();
+
+            // if <cond> { ... } else if <cond> { ... } else { ... }
+            let if_ = recursive(|if_| {
+                let else_branch = if_.or(block.clone()).recover_with(via_parser(
+                    clause
+                        .clone()
+                        .map_with(|_, e| Spanned::new(Statement::Error, e.span())),
+                ));
+                keyword("if")
+                    .ignore_then(expr.clone())
+                    .then(block.clone())
+                    .then(keyword("else").ignore_then(else_branch).or_not())
+                    .map_with(|((condition, then_branch), else_branch), e| {
+                        Spanned::new(
+                            Statement::If {
+                                condition,
+                                then_branch: Box::new(then_branch),
+                                else_branch: else_branch.map(Box::new),
+                            },
+                            e.span(),
+                        )
+                    })
+            })
+            .boxed();
+
+            // The body of a case clause runs until the next `when`, `else` or closing brace
+            let clause_body = keyword("when")
+                .or(keyword("else"))
+                .not()
+                .ignore_then(statement.clone())
+                .then_ignore(terminator())
+                .repeated()
+                .collect::<Vec<_>>()
+                .map_with(|statements, e| Spanned::new(Statement::Block(statements), e.span()))
+                .boxed();
+
+            // case [<expr>] { when a, b: ... else: ... }
+            let when = keyword("when")
+                .ignore_then(
+                    expr.clone()
+                        .separated_by(ctrl(','))
+                        .at_least(1)
+                        .collect::<Vec<_>>(),
+                )
+                .then_ignore(ctrl(':'))
+                .then(clause_body.clone());
+            // A subject never starts with `{`, which would otherwise be read as a map
+            // literal holding the clauses
+            let case = keyword("case")
+                .ignore_then(ctrl('{').not().ignore_then(expr.clone()).or_not())
+                .then_ignore(open_brace())
+                .then(when.repeated().collect::<Vec<_>>())
+                .then(
+                    keyword("else")
+                        .then(ctrl(':'))
+                        .ignore_then(clause_body)
+                        .or_not(),
+                )
+                .then_ignore(ctrl('}'))
+                .map(|((expr, clauses), else_clause)| Statement::Case {
+                    expr,
+                    clauses,
+                    else_clause: else_clause.map(Box::new),
+                })
+                .boxed();
+
+            // for <collection> as [<key>,] <value> { ... }
+            let for_ = keyword("for")
+                .ignore_then(expr.clone())
+                .then_ignore(keyword("as"))
+                .then(Identifier::name().then_ignore(ctrl(',')).or_not())
+                .then(Identifier::name())
+                .then(block.clone())
+                .map(|(((collection, key), value), body)| Statement::For {
+                    collection,
+                    key,
+                    value,
+                    body: Box::new(body),
+                })
+                .boxed();
+
+            let break_ = keyword("break").to(Statement::Break);
+            let continue_ = keyword("continue").to(Statement::Continue);
+
+            // The returned expression must start on the same line as the `return` keyword,
+            // otherwise the lexer ends the statement with a newline token
+            let return_ = keyword("return")
+                .ignore_then(choice((
+                    just(Token::Newline).rewind().to(None),
+                    expr.clone().or_not(),
+                )))
+                .map(Statement::Return);
+
+            // x = 1, x += 1, m["k"] = v. The target is parsed as an expression, which
+            // becomes an expression statement if no assignment operator follows, so each
+            // statement is only parsed once. Targets are checked afterwards, so that
+            // `f(x) = 1` is reported as an invalid target rather than as a syntax error.
+            // A target that failed to parse, such as a reserved word, has already been
+            // reported.
+            let operator = choice((
+                op("=").to(None),
+                op("+=").to(Some(BinaryOperator::Add)),
+                op("-=").to(Some(BinaryOperator::Subtract)),
+                op("*=").to(Some(BinaryOperator::Multiply)),
+                op("/=").to(Some(BinaryOperator::Divide)),
+                op("%=").to(Some(BinaryOperator::Modulus)),
+            ));
+            let assignment_or_expression = expr
+                .clone()
+                .then(operator.then(expr.clone()).or_not())
+                .validate(|(target, assignment), _, emitter| match assignment {
+                    None => Statement::Expression(target),
+                    Some((op, value)) => {
+                        if target.node != Expression::Error && !target.is_assignable() {
+                            emitter.emit(Rich::custom(
+                                target.span,
+                                "invalid assignment target: only variables and their indexes \
+                                 or fields can be assigned",
+                            ))
+                        }
+                        Statement::Assignment { target, op, value }
+                    }
+                });
+
+            // Tokens that end the statements of a block or case clause
+            let boundary = choice((ctrl('}'), keyword("when"), keyword("else"), end()));
+
+            // An `if`, `case` or `for` that fails to parse is skipped along with its body,
+            // rather than its keyword being read again as an expression statement.
+            // Without a body, the keyword was most likely used as a name, which the
+            // expression reports.
+            let skip_if = keyword("if")
+                .then(clause.clone())
+                .then(keyword("else").then(clause.clone()).repeated())
+                .to(Statement::Error);
+            let skip = |word| keyword(word).then(clause.clone()).to(Statement::Error);
+
+            choice((
+                if_.map(|statement| statement.node)
+                    .recover_with(via_parser(skip_if)),
+                case.recover_with(via_parser(skip("case"))),
+                for_.recover_with(via_parser(skip("for"))),
+                break_,
+                continue_,
+                return_,
+                block.map(|statement| statement.node),
+                assignment_or_expression,
+            ))
+            .map_with(|statement, e| Spanned::new(statement, e.span()))
+            // A statement that fails to parse is dropped up to the end of its line, so
+            // that only the first error in it is reported
+            .recover_with(via_parser(
+                skip_statement(boundary).map_with(|_, e| Spanned::new(Statement::Error, e.span())),
+            ))
+            .boxed()
+        })
+    }
+}
+
+impl Spanned<Statement> {
+    /// The span of the first `break` or `continue` in this statement outside of any loop
+    /// nested within it. As with returns, function bodies are not searched.
+    pub fn loop_control(&self) -> Option<Span> {
+        match &self.node {
+            Statement::Break | Statement::Continue => Some(self.span),
+            Statement::Block(statements) => statements.iter().find_map(|s| s.loop_control()),
+            Statement::If {
+                then_branch,
+                else_branch,
+                ..
+            } => then_branch
+                .loop_control()
+                .or_else(|| else_branch.as_ref().and_then(|s| s.loop_control())),
+            Statement::Case {
+                clauses,
+                else_clause,
+                ..
+            } => clauses
+                .iter()
+                .find_map(|(_, body)| body.loop_control())
+                .or_else(|| else_clause.as_ref().and_then(|s| s.loop_control())),
+            Statement::For { .. }
+            | Statement::Expression(_)
+            | Statement::Assignment { .. }
+            | Statement::Return(_)
+            | Statement::Error => None,
+        }
+    }
+}
+
+impl Statement {
+    /// Whether a `return` appears in this statement or any statement nested within it.
+    /// Function literals are expressions, so returns inside their bodies are not counted.
+    pub fn contains_return(&self) -> bool {
+        match self {
+            Statement::Return(_) => true,
+            Statement::Block(statements) => statements.iter().any(|s| s.contains_return()),
+            Statement::If {
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                then_branch.contains_return()
+                    || else_branch.as_ref().is_some_and(|s| s.contains_return())
+            }
+            Statement::Case {
+                clauses,
+                else_clause,
+                ..
+            } => {
+                clauses.iter().any(|(_, body)| body.contains_return())
+                    || else_clause.as_ref().is_some_and(|s| s.contains_return())
+            }
+            Statement::For { body, .. } => body.contains_return(),
+            Statement::Expression(_)
+            | Statement::Assignment { .. }
+            | Statement::Break
+            | Statement::Continue
+            | Statement::Error => false,
+        }
+    }
+
+    /// Create a new block statement from a list of statements
+    pub fn block(statements: Vec<Statement>) -> Self {
+        Statement::Block(statements.into_iter().map(Spanned::from).collect())
+    }
+
+    /// Create a new assignment statement
+    pub fn assignment(target: Expression, value: Expression) -> Self {
+        Statement::Assignment {
+            target: target.into(),
+            op: None,
+            value: value.into(),
+        }
+    }
+
+    /// Create a new compound assignment statement, e.g. `x += 1`
+    pub fn compound_assignment(target: Expression, op: BinaryOperator, value: Expression) -> Self {
+        Statement::Assignment {
+            target: target.into(),
+            op: Some(op),
+            value: value.into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{
+        ident, int, parse, test_parser, test_recovery, BinaryOperator, Expect, FileId, Literal,
+        UnaryOperator,
+    };
+
+    #[test]
+    fn test_expression_statement() {
+        test_parser(
+            "foobar(a)",
+            Statement::Expression(
+                Expression::call(Identifier::new("foobar"), vec![ident("a")]).into(),
+            ),
+        );
+        test_parser(
+            "x == 1",
+            Statement::Expression(
+                Expression::binary_expr(ident("x"), BinaryOperator::Equals, int(1)).into(),
+            ),
+        );
+    }
+
+    #[test]
+    fn test_assignment() {
+        test_parser("x = 1", Statement::assignment(ident("x"), int(1)));
+        test_parser::<Statement, &str>("if = 1", "`if` is a reserved keyword");
+        test_parser::<Statement, &str>(
+            "x = default",
+            "`default` is a reserved keyword and cannot be used as a name",
+        );
+        test_parser(
+            "x=a+1",
+            Statement::assignment(
+                ident("x"),
+                Expression::binary_expr(ident("a"), BinaryOperator::Add, int(1)),
+            ),
+        );
+    }
+
+    #[test]
+    fn test_compound_assignment() {
+        test_parser(
+            "x += 1",
+            Statement::compound_assignment(ident("x"), BinaryOperator::Add, int(1)),
+        );
+        test_parser(
+            "x -= y * 2",
+            Statement::compound_assignment(
+                ident("x"),
+                BinaryOperator::Subtract,
+                Expression::binary_expr(ident("y"), BinaryOperator::Multiply, int(2)),
+            ),
+        );
+        test_parser(
+            "x *= 2",
+            Statement::compound_assignment(ident("x"), BinaryOperator::Multiply, int(2)),
+        );
+        test_parser(
+            "x /= 2",
+            Statement::compound_assignment(ident("x"), BinaryOperator::Divide, int(2)),
+        );
+        test_parser(
+            "x %= 2",
+            Statement::compound_assignment(ident("x"), BinaryOperator::Modulus, int(2)),
+        );
+    }
+
+    #[test]
+    fn test_indexed_assignment() {
+        test_parser(
+            "m[\"k\"] = v",
+            Statement::assignment(
+                Expression::index(
+                    ident("m"),
+                    Expression::Literal(Literal::String("k".to_string().into())),
+                ),
+                ident("v"),
+            ),
+        );
+        test_parser(
+            "list[0] += 1",
+            Statement::compound_assignment(
+                Expression::index(ident("list"), int(0)),
+                BinaryOperator::Add,
+                int(1),
+            ),
+        );
+        test_parser(
+            "a.b[i] = 1",
+            Statement::assignment(
+                Expression::index(
+                    Expression::select(ident("a"), Identifier::new("b")),
+                    ident("i"),
+                ),
+                int(1),
+            ),
+        );
+    }
+
+    #[test]
+    fn test_nested_function_bodies() {
+        // Each statement is parsed once, so nesting does not multiply the work
+        let mut source = "x".to_string();
+        for _ in 0..16 {
+            source = format!("f(func() {{ {} }})", source);
+        }
+        let (statement, errors) = parse::<Statement>(&source, FileId::default());
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert!(matches!(statement, Some(Statement::Expression(_))));
+    }
+
+    #[test]
+    fn test_invalid_assignment_target() {
+        test_parser::<Statement, &str>("f(x) = 1", "invalid assignment target");
+        test_parser::<Statement, &str>("1 = 2", "invalid assignment target");
+        test_parser::<Statement, &str>("a + b += 1", "invalid assignment target");
+        test_parser::<Statement, &str>("xs[0:1] = []", "invalid assignment target");
+        test_parser::<Statement, &str>("default += 1", "`default` is a reserved keyword");
+    }
+
+    #[test]
+    fn test_rule_assignment() {
+        test_parser(
+            "main = rule {\n    is_tagged and\n    rule { is_private }\n}",
+            Statement::assignment(
+                ident("main"),
+                Expression::rule(
+                    None,
+                    Expression::binary_expr(
+                        ident("is_tagged"),
+                        BinaryOperator::And,
+                        Expression::rule(None, ident("is_private")),
+                    ),
+                ),
+            ),
+        );
+    }
+
+    #[test]
+    fn test_keyword_expression_statement() {
+        test_parser(
+            "true",
+            Statement::Expression(Expression::Literal(Literal::Boolean(true)).into()),
+        );
+        test_parser(
+            "null == x",
+            Statement::Expression(
+                Expression::binary_expr(
+                    Expression::Literal(Literal::Null),
+                    BinaryOperator::Equals,
+                    ident("x"),
+                )
+                .into(),
+            ),
+        );
+    }
+
+    #[test]
+    fn test_block() {
+        test_parser("{}", Statement::block(vec![]));
+        test_parser(
+            "{\n    x = 1\n    y = 2\n}",
+            Statement::block(vec![
+                Statement::assignment(ident("x"), int(1)),
+                Statement::assignment(ident("y"), int(2)),
+            ]),
+        );
+    }
+
+    #[test]
+    fn test_line_breaks() {
+        // A line break ends a statement before a line that could start a new one
+        test_parser(
+            "{\n    x = a\n    !b\n}",
+            Statement::block(vec![
+                Statement::assignment(ident("x"), ident("a")),
+                Statement::Expression(
+                    Expression::unary_expr(UnaryOperator::Not, ident("b")).into(),
+                ),
+            ]),
+        );
+        // A line starting with `-` or `+` continues the expression above it
+        test_parser(
+            "{\n    x = a\n      - b\n}",
+            Statement::block(vec![Statement::assignment(
+                ident("x"),
+                Expression::binary_expr(ident("a"), BinaryOperator::Subtract, ident("b")),
+            )]),
+        );
+        test_parser(
+            "{\n    x = a\n      + b\n}",
+            Statement::block(vec![Statement::assignment(
+                ident("x"),
+                Expression::binary_expr(ident("a"), BinaryOperator::Add, ident("b")),
+            )]),
+        );
+        test_parser(
+            "{\n    x = f\n    (y)\n}",
+            Statement::block(vec![
+                Statement::assignment(ident("x"), ident("f")),
+                Statement::Expression(ident("y").into()),
+            ]),
+        );
+        test_parser(
+            "if a\n{\n    x = 1\n}",
+            Statement::If {
+                condition: ident("a").into(),
+                then_branch: Box::new(
+                    Statement::block(vec![Statement::assignment(ident("x"), int(1))]).into(),
+                ),
+                else_branch: None,
+            },
+        );
+        test_recovery(
+            "{ x = 1 y = 2 }",
+            Statement::block(vec![
+                Statement::assignment(ident("x"), int(1)),
+                Statement::assignment(ident("y"), int(2)),
+            ]),
+            &["expected a line break before the next statement"],
+        );
+    }
+
+    #[test]
+    fn test_block_recovery() {
+        // A statement that fails to parse becomes an error node up to the end of its
+        // line, or the closing brace of its block
+        test_recovery(
+            "{\n    ) x = 1\n    y = 2 *\n}",
+            Statement::block(vec![
+                Statement::Error,
+                Statement::assignment(ident("y"), int(2)),
+                Statement::Error,
+            ]),
+            &["found ) expected", "found } expected"],
+        );
+        test_recovery(
+            "if x {\n  y = (1 +\n}",
+            Statement::If {
+                condition: ident("x").into(),
+                then_branch: Box::new(
+                    Statement::block(vec![Statement::assignment(ident("y"), Expression::Error)])
+                        .into(),
+                ),
+                else_branch: None,
+            },
+            &["found } expected '+', '-', '!', 'not', or an expression"],
+        );
+        test_recovery(
+            "case x {\n    when 1: )\n    when 2: y = 2\n}",
+            Statement::Case {
+                expr: Some(ident("x").into()),
+                clauses: vec![
+                    (
+                        vec![int(1).into()],
+                        Statement::block(vec![Statement::Error]).into(),
+                    ),
+                    (
+                        vec![int(2).into()],
+                        Statement::block(vec![Statement::assignment(ident("y"), int(2))]).into(),
+                    ),
+                ],
+                else_clause: None,
+            },
+            &["found ) expected"],
+        );
+        // A statement led by a keyword is skipped along with its body, which runs to
+        // the end of the input when it is never closed
+        for input in [
+            "if x { y = 1 ",
+            "for xs as x {\n  y = 1\n",
+            "case x {\n  when 1:\n    y = 1\n",
+        ] {
+            test_recovery(input, Statement::Error, &["found end of input expected"]);
+        }
+        test_recovery(
+            "if x { y = 1 } else {\n  y = 2\n",
+            Statement::If {
+                condition: ident("x").into(),
+                then_branch: Box::new(
+                    Statement::block(vec![Statement::assignment(ident("y"), int(1))]).into(),
+                ),
+                else_branch: Some(Box::new(Statement::Error.into())),
+            },
+            &["found end of input expected"],
+        );
+        test_recovery(
+            "{\n  for xs as x y {\n    z = 1\n  }\n  y = 2\n}",
+            Statement::block(vec![
+                Statement::Error,
+                Statement::assignment(ident("y"), int(2)),
+            ]),
+            &["found y expected"],
+        );
+    }
+
+    #[test]
+    fn test_else_operator_in_statements() {
+        // `else` is both a binary operator and part of `if` and `case` statements
+        let fallback = || Expression::binary_expr(ident("a"), BinaryOperator::Else, ident("b"));
+        test_parser(
+            "if a else b { y = a else b } else { y = 2 }",
+            Statement::If {
+                condition: fallback().into(),
+                then_branch: Box::new(
+                    Statement::block(vec![Statement::assignment(ident("y"), fallback())]).into(),
+                ),
+                else_branch: Some(Box::new(
+                    Statement::block(vec![Statement::assignment(ident("y"), int(2))]).into(),
+                )),
+            },
+        );
+        test_parser(
+            "case a else b {\n  when 1: y = a else b\n  else: y = 2\n}",
+            Statement::Case {
+                expr: Some(fallback().into()),
+                clauses: vec![(
+                    vec![int(1).into()],
+                    Statement::block(vec![Statement::assignment(ident("y"), fallback())]).into(),
+                )],
+                else_clause: Some(Box::new(
+                    Statement::block(vec![Statement::assignment(ident("y"), int(2))]).into(),
+                )),
+            },
+        );
+    }
+
+    #[test]
+    fn test_if() {
+        test_parser(
+            "if x { y = 1 }",
+            Statement::If {
+                condition: ident("x").into(),
+                then_branch: Box::new(
+                    Statement::block(vec![Statement::assignment(ident("y"), int(1))]).into(),
+                ),
+                else_branch: None,
+            },
+        );
+        test_parser(
+            "if x { y = 1 } else if z { y = 2 } else { y = 3 }",
+            Statement::If {
+                condition: ident("x").into(),
+                then_branch: Box::new(
+                    Statement::block(vec![Statement::assignment(ident("y"), int(1))]).into(),
+                ),
+                else_branch: Some(Box::new(
+                    Statement::If {
+                        condition: ident("z").into(),
+                        then_branch: Box::new(
+                            Statement::block(vec![Statement::assignment(ident("y"), int(2))])
+                                .into(),
+                        ),
+                        else_branch: Some(Box::new(
+                            Statement::block(vec![Statement::assignment(ident("y"), int(3))])
+                                .into(),
+                        )),
+                    }
+                    .into(),
+                )),
+            },
+        );
+    }
+
+    #[test]
+    fn test_case() {
+        test_parser(
+            "case x {\n  when 1, 2:\n    y = 1\n    break\n  when 3:\n    y = 2\n  else:\n    y = 3\n}",
+            Statement::Case {
+                expr: Some(ident("x").into()),
+                clauses: vec![
+                    (
+                        vec![int(1).into(), int(2).into()],
+                        Statement::block(vec![
+                            Statement::assignment(ident("y"), int(1)),
+                            Statement::Break,
+                        ])
+                        .into(),
+                    ),
+                    (
+                        vec![int(3).into()],
+                        Statement::block(vec![Statement::assignment(ident("y"), int(2))]).into(),
+                    ),
+                ],
+                else_clause: Some(Box::new(Statement::block(vec![Statement::assignment(
+                    ident("y"),
+                    int(3),
+                )]).into())),
+            },
+        );
+        test_parser(
+            "case { when x: return 1 }",
+            Statement::Case {
+                expr: None,
+                clauses: vec![(
+                    vec![ident("x").into()],
+                    Statement::block(vec![Statement::Return(Some(int(1).into()))]).into(),
+                )],
+                else_clause: None,
+            },
+        );
+    }
+
+    #[test]
+    fn test_for() {
+        test_parser(
+            "for coll as v { continue }",
+            Statement::For {
+                collection: ident("coll").into(),
+                key: None,
+                value: Identifier::new("v").into(),
+                body: Box::new(Statement::block(vec![Statement::Continue]).into()),
+            },
+        );
+        test_parser(
+            "for coll as k, v {\n  x = v\n}",
+            Statement::For {
+                collection: ident("coll").into(),
+                key: Some(Identifier::new("k").into()),
+                value: Identifier::new("v").into(),
+                body: Box::new(
+                    Statement::block(vec![Statement::assignment(ident("x"), ident("v"))]).into(),
+                ),
+            },
+        );
+    }
+
+    #[test]
+    fn test_return() {
+        test_parser("return", Statement::Return(None));
+        test_parser("return x", Statement::Return(Some(ident("x").into())));
+        test_parser(
+            "{\n  return\n  x\n}",
+            Statement::block(vec![
+                Statement::Return(None),
+                Statement::Expression(ident("x").into()),
+            ]),
+        );
+    }
+
+    impl From<Statement> for Expect<Statement> {
+        fn from(value: Statement) -> Self {
+            Expect::Something(value)
+        }
+    }
 }
@@ -0,0 +1,644 @@
+use crate::parser::{trivia, LexError, Literal, ParsableError, ParserInput, Span, KEYWORDS};
+use chumsky::prelude::*;
+use chumsky::text::Char;
+use chumsky::util::MaybeRef;
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+
+/// A single lexical unit of a policy. Whitespace and comments never reach the grammar,
+/// which only ever sees a stream of spanned tokens.
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) enum Token<'src> {
+    /// A float or string literal
+    Literal(Literal),
+    /// The magnitude of an integer literal, which may only fit in an `i64` once negated
+    Integer(u64),
+    Ident(&'src str),
+    Keyword(&'src str),
+    Op(&'src str),
+    Ctrl(char),
+    /// A line break that ends a statement, see `terminate_lines`
+    Newline,
+    /// The opening quote of a string containing interpolations
+    StringStart,
+    /// Text between the interpolations of a string, with escapes resolved
+    StringText(String),
+    /// The closing quote of a string containing interpolations
+    StringEnd,
+    /// The `${` that starts an interpolation
+    InterpolationStart,
+    /// The `}` that ends an interpolation
+    InterpolationEnd,
+}
+
+impl Token<'_> {
+    /// Whether a statement can end with this token
+    fn ends_statement(&self) -> bool {
+        match self {
+            Token::Literal(_)
+            | Token::Integer(_)
+            | Token::Ident(_)
+            | Token::StringEnd
+            | Token::Ctrl(')' | ']' | '}') => true,
+            Token::Keyword(word) => matches!(
+                *word,
+                "break"
+                    | "continue"
+                    | "return"
+                    | "empty"
+                    | "defined"
+                    | "true"
+                    | "false"
+                    | "null"
+                    | "undefined"
+            ),
+            _ => false,
+        }
+    }
+
+    /// Whether a statement, parameter or import can start with this token. Binary
+    /// operators such as `and` or `*` cannot, so an expression can be continued on
+    /// the next line by starting it with one. This includes `-` and `+`: a line such as
+    /// `- b` is read as the rest of the expression above it, since its value would
+    /// otherwise go unused.
+    pub(crate) fn starts_statement(&self) -> bool {
+        match self {
+            Token::Literal(_)
+            | Token::Integer(_)
+            | Token::Ident(_)
+            | Token::StringStart
+            | Token::Ctrl('(' | '[' | '{') => true,
+            Token::Op(symbol) => *symbol == "!",
+            Token::Keyword(word) => matches!(
+                *word,
+                "all"
+                    | "any"
+                    | "break"
+                    | "case"
+                    | "continue"
+                    | "false"
+                    | "filter"
+                    | "for"
+                    | "func"
+                    | "if"
+                    | "import"
+                    | "map"
+                    | "null"
+                    | "param"
+                    | "return"
+                    | "rule"
+                    | "true"
+                    | "undefined"
+            ),
+            _ => false,
+        }
+    }
+}
+
+impl Display for Token<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Literal(Literal::String(s)) => write!(f, "{:?}", s),
+            Token::Literal(Literal::Float(x)) => write!(f, "{:?}", x),
+            Token::Literal(literal) => write!(f, "{:?}", literal),
+            Token::Integer(i) => write!(f, "{}", i),
+            Token::Ident(s) | Token::Keyword(s) | Token::Op(s) => write!(f, "{}", s),
+            Token::Ctrl(c) => write!(f, "{}", c),
+            Token::Newline => write!(f, "newline"),
+            Token::StringStart | Token::StringEnd => write!(f, "\""),
+            Token::StringText(s) => write!(f, "{:?}", s),
+            Token::InterpolationStart => write!(f, "${{"),
+            Token::InterpolationEnd => write!(f, "}}"),
+        }
+    }
+}
+
+/// Splits source text into tokens, skipping whitespace and comments. Characters that
+/// cannot start a token are reported and skipped, so the grammar still gets to run.
+pub(crate) fn lexer<'src>(
+) -> impl Parser<'src, &'src str, Vec<(Token<'src>, SimpleSpan)>, LexError<'src>> {
+    let op = choice((
+        just("=="),
+        just("!="),
+        just("<="),
+        just(">="),
+        one_of("+-*/%").then(just('=')).to_slice(),
+        one_of("+-*/%<>=!").to_slice(),
+    ))
+    .map(Token::Op);
+
+    // Braces are lexed separately, as they must balance within an interpolation
+    let ctrl = one_of("()[],:.").map(Token::Ctrl);
+
+    // Identifiers follow the Unicode default identifier syntax (UAX #31) with `_` allowed
+    // as a first character, as in Rust: e.g. `_tmp`, `région` or `名前`
+    let word = any()
+        .filter(|c: &char| c.is_ident_start() || *c == '_')
+        .then(any().filter(|c: &char| c.is_ident_continue()).repeated())
+        .to_slice()
+        .map(|word: &str| {
+            if KEYWORDS.contains(&word) {
+                Token::Keyword(word)
+            } else {
+                Token::Ident(word)
+            }
+        });
+
+    let token =
+        choice((Literal::lexer(), op, ctrl, word)).map_with(|token, e| vec![(token, e.span())]);
+
+    // Every token up to the `}` that closes an interpolation, including any nested
+    // braces and interpolated strings
+    let nested = recursive(|nested| {
+        let delimited = |open: &'static str, start: Token<'src>, end: Token<'src>| {
+            just(open)
+                .map_with(move |_, e| (start.clone(), e.span()))
+                .then_ignore(trivia())
+                .then(
+                    skip_invalid(nested.clone(), "}\"")
+                        .repeated()
+                        .collect::<Vec<_>>(),
+                )
+                .then(
+                    just("}")
+                        .map_with(move |_, e| (end.clone(), e.span()))
+                        .or_not(),
+                )
+        };
+        // An interpolation left open is closed where its tokens run out, which is usually
+        // the end of the string, so that the rest of the string still lexes
+        let interpolation = delimited("${", Token::InterpolationStart, Token::InterpolationEnd)
+            .validate(|((open, tokens), close), e, emitter| {
+                let span: SimpleSpan = e.span();
+                let close = close.unwrap_or_else(|| {
+                    emitter.emit(Rich::custom(span, "unterminated `${`"));
+                    (
+                        Token::InterpolationEnd,
+                        SimpleSpan::from(span.end..span.end),
+                    )
+                });
+                enclose(open, tokens, close)
+            });
+        let braces = delimited("{", Token::Ctrl('{'), Token::Ctrl('}')).try_map(
+            |((open, tokens), close), span| match close {
+                Some(close) => Ok(enclose(open, tokens, close)),
+                None => Err(Rich::custom(span, "unclosed `{`")),
+            },
+        );
+
+        choice((token, template(interpolation), braces)).then_ignore(trivia())
+    });
+    let brace = one_of("{}")
+        .map_with(|c, e| vec![(Token::Ctrl(c), e.span())])
+        .then_ignore(trivia());
+
+    // A string left open runs to the end of the input, and is reported once as a whole
+    // rather than by every token that could have closed it
+    let open_string =
+        just('"')
+            .ignore_then(any().repeated().to_slice())
+            .validate(|text: &str, e, emitter| {
+                emitter.emit(Rich::custom(e.span(), "unterminated string"));
+                let literal = Literal::String(Arc::new(text.to_string()));
+                vec![(Token::Literal(literal), e.span())]
+            });
+
+    trivia()
+        .ignore_then(
+            skip_invalid(choice((brace, nested, open_string)), "}")
+                .repeated()
+                .collect::<Vec<_>>(),
+        )
+        .map_with(|tokens, e| {
+            let span: SimpleSpan = e.span();
+            terminate_lines(
+                tokens.into_iter().flatten().collect(),
+                e.slice(),
+                span.start,
+            )
+        })
+}
+
+type SpannedTokens<'src> = Vec<(Token<'src>, SimpleSpan)>;
+
+/// Reports and drops a character that cannot start a token, even at the end of the
+/// input, so that lexing carries on after it. The character is named on its own rather
+/// than next to every character that could have started a token. Characters in `stop`
+/// are never dropped, such as a `}` that closes an interpolation.
+fn skip_invalid<'src>(
+    token: impl Parser<'src, &'src str, SpannedTokens<'src>, LexError<'src>> + Clone,
+    stop: &'static str,
+) -> impl Parser<'src, &'src str, SpannedTokens<'src>, LexError<'src>> + Clone {
+    token
+        .map_err_with_state(|e: Rich<char>, span: SimpleSpan, _| match e.found() {
+            Some(c) if e.span().start == span.start => {
+                Rich::custom(*e.span(), format!("unexpected character `{}`", c))
+            }
+            _ => e,
+        })
+        .recover_with(via_parser(none_of(stop).to(vec![]).then_ignore(trivia())))
+}
+
+/// Lexes a string containing `${...}` interpolations. The string becomes a
+/// `StringStart` token, its text and interpolated tokens in order, and a `StringEnd`
+/// token.
+fn template<'src>(
+    interpolation: impl Parser<'src, &'src str, SpannedTokens<'src>, LexError<'src>> + Clone,
+) -> impl Parser<'src, &'src str, SpannedTokens<'src>, LexError<'src>> + Clone {
+    let text = Literal::string_char()
+        .repeated()
+        .at_least(1)
+        .collect::<String>()
+        .map_with(|text, e| vec![(Token::StringText(text), e.span())]);
+
+    just('"')
+        .map_with(|_, e| (Token::StringStart, e.span()))
+        .then(choice((text, interpolation)).repeated().collect::<Vec<_>>())
+        .then(just('"').map_with(|_, e| (Token::StringEnd, e.span())))
+        .map(|((start, parts), end)| enclose(start, parts, end))
+}
+
+/// Joins groups of tokens between an opening and a closing token
+fn enclose<'src>(
+    open: (Token<'src>, SimpleSpan),
+    tokens: Vec<SpannedTokens<'src>>,
+    close: (Token<'src>, SimpleSpan),
+) -> SpannedTokens<'src> {
+    std::iter::once(open)
+        .chain(tokens.into_iter().flatten())
+        .chain(std::iter::once(close))
+        .collect()
+}
+
+/// Inserts a `Newline` at every line break that ends a statement: one between a token
+/// that can end a statement and a token that can start one, outside of parentheses,
+/// brackets and interpolations. This keeps `x = a` and `!b` on the next line apart. A
+/// bare `return` is always terminated by a line break, so that it does not take the
+/// expression on the next line as its value. A keyword after a `.` is a field name,
+/// as in `r.return`.
+fn terminate_lines<'src>(
+    tokens: Vec<(Token<'src>, SimpleSpan)>,
+    source: &str,
+    offset: usize,
+) -> Vec<(Token<'src>, SimpleSpan)> {
+    let mut result = Vec::with_capacity(tokens.len());
+    // The delimiters enclosing the current token
+    let mut open = vec![];
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some((token, span)) = tokens.next() {
+        match token {
+            Token::Ctrl('(' | '[' | '{') | Token::InterpolationStart => open.push(token.clone()),
+            Token::Ctrl(')' | ']' | '}') | Token::InterpolationEnd => {
+                open.pop();
+            }
+            _ => {}
+        }
+        let field = result.last().map(|(previous, _)| previous) == Some(&Token::Ctrl('.'));
+        let bare_return = token == Token::Keyword("return") && !field;
+        let statements = matches!(open.last(), None | Some(Token::Ctrl('{')));
+        let terminated = tokens.peek().is_some_and(|(next, next_span)| {
+            source[span.end - offset..next_span.start - offset].contains('\n')
+                && (bare_return
+                    || statements
+                        && (token.ends_statement() || field && matches!(token, Token::Keyword(_)))
+                        && next.starts_statement())
+        });
+        result.push((token, span));
+        if terminated {
+            result.push((Token::Newline, SimpleSpan::from(span.end..span.end)));
+        }
+    }
+    result
+}
+
+/// Whether the word would be lexed as an identifier, rather than a keyword or several
+/// tokens
+pub(crate) fn is_identifier(word: &str) -> bool {
+    let mut chars = word.chars();
+    chars.next().is_some_and(|c| c.is_ident_start() || c == '_')
+        && chars.all(|c| c.is_ident_continue())
+        && !KEYWORDS.contains(&word)
+}
+
+/// Matches a single token against patterns, like `select!`. A token that does not
+/// match is reported at its own position, whereas `select!` reports it after the token,
+/// where it would outrank and hide the errors of the alternatives tried alongside it.
+macro_rules! select_token {
+    ($($pattern:pat $(if $guard:expr)? => $out:expr),+ $(,)?) => {
+        chumsky::prelude::any().try_map(move |token, span| match token {
+            $($pattern $(if $guard)? => Ok($out),)+
+            token => Err($crate::parser::unexpected(token, span)),
+        })
+    };
+}
+pub(crate) use select_token;
+
+/// The error for a token that none of the alternatives tried at it expected
+pub(crate) fn unexpected<'src>(token: Token<'src>, span: Span) -> Rich<'src, Token<'src>, Span> {
+    <Rich<_, _> as chumsky::error::Error<ParserInput<'src>>>::expected_found(
+        std::iter::empty(),
+        Some(MaybeRef::Val(token)),
+        span,
+    )
+}
+
+/// Matches a reserved word, e.g. `if`
+pub(crate) fn keyword<'src>(
+    word: &'static str,
+) -> impl Parser<'src, ParserInput<'src>, (), ParsableError<'src>> + Clone {
+    just(Token::Keyword(word)).ignored()
+}
+
+/// Matches the `{` that opens a body, which may also start the line after its header,
+/// e.g. `if x` followed by `{` on the next line
+pub(crate) fn open_brace<'src>(
+) -> impl Parser<'src, ParserInput<'src>, (), ParsableError<'src>> + Clone {
+    just(Token::Newline).or_not().ignore_then(ctrl('{'))
+}
+
+/// Ends a statement, parameter or import. Another one may only follow on a new line,
+/// while anything else is left for the enclosing parser to accept or report.
+pub(crate) fn terminator<'src>(
+) -> impl Parser<'src, ParserInput<'src>, (), ParsableError<'src>> + Clone {
+    choice((
+        just(Token::Newline).ignored(),
+        select_token! { token if Token::starts_statement(&token) => () }
+            .map_with(|_, e| e.span())
+            .rewind()
+            .validate(|span, _, emitter| {
+                emitter.emit(Rich::custom(
+                    span,
+                    "expected a line break before the next statement",
+                ))
+            }),
+        empty(),
+    ))
+}
+
+/// Matches an operator, e.g. `+` or `==`
+pub(crate) fn op<'src>(
+    symbol: &'static str,
+) -> impl Parser<'src, ParserInput<'src>, (), ParsableError<'src>> + Clone {
+    just(Token::Op(symbol)).ignored()
+}
+
+/// Matches a punctuation character, e.g. `(` or `,`
+pub(crate) fn ctrl<'src>(
+    c: char,
+) -> impl Parser<'src, ParserInput<'src>, (), ParsableError<'src>> + Clone {
+    just(Token::Ctrl(c)).ignored()
+}
+
+/// Recovers from a malformed group by skipping to the delimiter that closes `open`,
+/// taking nested brackets into account. A group that is never closed runs to the end
+/// of the enclosing group or of the input instead. The skipped tokens become
+/// `fallback`.
+pub(crate) fn balanced<'src, O>(
+    open: char,
+    close: char,
+    fallback: impl Fn(Span) -> O + Clone,
+) -> impl Parser<'src, ParserInput<'src>, O, ParsableError<'src>> + Clone {
+    let unclosed = fallback.clone();
+    choice((
+        nested_delimiters(
+            Token::Ctrl(open),
+            Token::Ctrl(close),
+            [
+                (Token::Ctrl('('), Token::Ctrl(')')),
+                (Token::Ctrl('['), Token::Ctrl(']')),
+                (Token::Ctrl('{'), Token::Ctrl('}')),
+            ],
+            fallback,
+        ),
+        ctrl(open)
+            .then(choice((group(), any().and_is(closing().not()).ignored())).repeated())
+            .map_with(move |_, e| unclosed(e.span())),
+    ))
+}
+
+/// Matches a bracket that closes a group
+fn closing<'src>() -> impl Parser<'src, ParserInput<'src>, (), ParsableError<'src>> + Clone {
+    choice((ctrl(')'), ctrl(']'), ctrl('}')))
+}
+
+/// Skips a bracketed group and everything nested within it
+fn group<'src>() -> impl Parser<'src, ParserInput<'src>, (), ParsableError<'src>> + Clone {
+    recursive(|group| {
+        let inner = choice((group, any().and_is(closing().not()).ignored())).repeated();
+        choice((
+            ctrl('(').then(inner.clone()).then(ctrl(')')),
+            ctrl('[').then(inner.clone()).then(ctrl(']')),
+            ctrl('{').then(inner).then(ctrl('}')),
+        ))
+        .ignored()
+    })
+}
+
+/// Skips the rest of a statement that failed to parse, up to the end of its line or
+/// the end of the enclosing block. Bracketed groups are skipped as a whole, so that a
+/// function body within the statement does not end the enclosing block early, while an
+/// unclosed bracket is skipped like any other token.
+pub(crate) fn skip_statement<'src>(
+    boundary: impl Parser<'src, ParserInput<'src>, (), ParsableError<'src>> + Clone,
+) -> impl Parser<'src, ParserInput<'src>, (), ParsableError<'src>> + Clone {
+    choice((
+        group(),
+        any()
+            .and_is(boundary.or(just(Token::Newline).ignored()).not())
+            .ignored(),
+    ))
+    .repeated()
+    .at_least(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    fn lex(input: &str) -> Vec<Token<'_>> {
+        lexer()
+            .parse(input)
+            .into_result()
+            .expect("input should lex")
+            .into_iter()
+            .map(|(token, _)| token)
+            .collect()
+    }
+
+    #[test]
+    fn test_lex() {
+        assert_eq!(
+            lex("x = a.b[1] != \"s\" // done"),
+            vec![
+                Token::Ident("x"),
+                Token::Op("="),
+                Token::Ident("a"),
+                Token::Ctrl('.'),
+                Token::Ident("b"),
+                Token::Ctrl('['),
+                Token::Integer(1),
+                Token::Ctrl(']'),
+                Token::Op("!="),
+                Token::Literal(Literal::String(Arc::new("s".to_string()))),
+            ]
+        );
+        assert_eq!(
+            lex("is not empty isnt"),
+            vec![
+                Token::Keyword("is"),
+                Token::Keyword("not"),
+                Token::Keyword("empty"),
+                Token::Ident("isnt"),
+            ]
+        );
+        assert_eq!(lex("  /* nothing */  "), vec![]);
+        assert_eq!(
+            lex("x += 1 -= *= /= %=-1"),
+            vec![
+                Token::Ident("x"),
+                Token::Op("+="),
+                Token::Integer(1),
+                Token::Op("-="),
+                Token::Op("*="),
+                Token::Op("/="),
+                Token::Op("%="),
+                Token::Op("-"),
+                Token::Integer(1),
+            ]
+        );
+        assert_eq!(
+            lex(r#""a ${ {"b": x}["b"] }""#),
+            vec![
+                Token::StringStart,
+                Token::StringText("a ".to_string()),
+                Token::InterpolationStart,
+                Token::Ctrl('{'),
+                Token::Literal(Literal::String(Arc::new("b".to_string()))),
+                Token::Ctrl(':'),
+                Token::Ident("x"),
+                Token::Ctrl('}'),
+                Token::Ctrl('['),
+                Token::Literal(Literal::String(Arc::new("b".to_string()))),
+                Token::Ctrl(']'),
+                Token::InterpolationEnd,
+                Token::StringEnd,
+            ]
+        );
+        assert_eq!(
+            lex("doc = <<EOF\n  a\n  EOF\nre = `\\d`"),
+            vec![
+                Token::Ident("doc"),
+                Token::Op("="),
+                Token::Literal(Literal::String(Arc::new("a\n".to_string()))),
+                Token::Newline,
+                Token::Ident("re"),
+                Token::Op("="),
+                Token::Literal(Literal::String(Arc::new("\\d".to_string()))),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_spans() {
+        let spans = lexer()
+            .parse("a  >= 10")
+            .into_result()
+            .unwrap()
+            .into_iter()
+            .map(|(_, span)| span.into_range())
+            .collect::<Vec<_>>();
+        assert_eq!(spans, vec![0..1, 3..5, 6..8]);
+    }
+
+    #[test]
+    fn test_bare_return() {
+        assert_eq!(
+            lex("return # nothing\nx"),
+            vec![Token::Keyword("return"), Token::Newline, Token::Ident("x")]
+        );
+        assert_eq!(
+            lex("return x"),
+            vec![Token::Keyword("return"), Token::Ident("x")]
+        );
+        assert_eq!(
+            lex("r.return\nb"),
+            vec![
+                Token::Ident("r"),
+                Token::Ctrl('.'),
+                Token::Keyword("return"),
+                Token::Newline,
+                Token::Ident("b"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_line_breaks() {
+        assert_eq!(
+            lex("x = a\n!b"),
+            vec![
+                Token::Ident("x"),
+                Token::Op("="),
+                Token::Ident("a"),
+                Token::Newline,
+                Token::Op("!"),
+                Token::Ident("b"),
+            ]
+        );
+        // A line starting with a binary operator continues the previous one
+        for input in ["x = a\n  and b", "x = a\n  - b", "x = a\n  + b"] {
+            assert!(!lex(input).contains(&Token::Newline), "input: {:?}", input);
+        }
+        // Nothing within parentheses or brackets ends a statement, but a function body
+        // within them does
+        assert_eq!(
+            lex("f(a\n(b)) {x\n[y]}"),
+            vec![
+                Token::Ident("f"),
+                Token::Ctrl('('),
+                Token::Ident("a"),
+                Token::Ctrl('('),
+                Token::Ident("b"),
+                Token::Ctrl(')'),
+                Token::Ctrl(')'),
+                Token::Ctrl('{'),
+                Token::Ident("x"),
+                Token::Newline,
+                Token::Ctrl('['),
+                Token::Ident("y"),
+                Token::Ctrl(']'),
+                Token::Ctrl('}'),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_character() {
+        let (tokens, errors) = lexer().parse("a @ b").into_output_errors();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].span().into_range(), 2..3);
+        assert_eq!(tokens.unwrap().len(), 2);
+
+        assert_eq!(errors[0].to_string(), "unexpected character `@`");
+
+        let (tokens, errors) = lexer().parse("a @").into_output_errors();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            tokens.unwrap(),
+            vec![(Token::Ident("a"), SimpleSpan::from(0..1))]
+        );
+
+        // A statement separator from other languages is named on its own, as is a
+        // character within an interpolation
+        let (_, errors) = lexer().parse("x = 1; y = 2").into_output_errors();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].span().into_range(), 5..6);
+        assert_eq!(errors[0].to_string(), "unexpected character `;`");
+
+        let (tokens, errors) = lexer().parse("\"${a;}\"").into_output_errors();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].to_string(), "unexpected character `;`");
+        assert_eq!(tokens.unwrap().len(), 5);
+    }
+}
@@ -1,6 +1,5 @@
-use crate::parser::{Parsable, ParsableError};
+use crate::parser::{keyword, op, select_token, Parsable, ParsableError, ParserInput, Token};
 use chumsky::prelude::*;
-use chumsky::Parser;
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum UnaryOperator {
@@ -14,15 +13,60 @@ pub enum UnaryOperator {
 }
 
 impl Parsable for UnaryOperator {
-    fn parser<'src>() -> impl Parser<'src, &'src str, Self, ParsableError<'src>> {
+    fn parser<'src>() -> impl Parser<'src, ParserInput<'src>, Self, ParsableError<'src>> {
+        choice((Self::prefix(), Self::postfix()))
+    }
+}
+
+impl UnaryOperator {
+    /// Operators written before their operand
+    pub(crate) fn prefix<'src>() -> impl Parser<'src, ParserInput<'src>, Self, ParsableError<'src>>
+    {
         choice((
-            just("+").to(UnaryOperator::Plus),
-            just("-").to(UnaryOperator::Minus),
-            just("!").to(UnaryOperator::Not),
-            just("is empty").to(UnaryOperator::IsEmpty),
-            just("is not empty").to(UnaryOperator::IsNotEmpty),
-            just("is defined").to(UnaryOperator::IsDefined),
-            just("is not defined").to(UnaryOperator::IsNotDefined),
+            op("+").to(UnaryOperator::Plus),
+            op("-").to(UnaryOperator::Minus),
+            op("!").to(UnaryOperator::Not),
+            keyword("not").to(UnaryOperator::Not),
         ))
     }
+
+    /// Operators written after their operand, e.g. `tags is not empty`
+    pub(crate) fn postfix<'src>() -> impl Parser<'src, ParserInput<'src>, Self, ParsableError<'src>>
+    {
+        keyword("is")
+            .ignore_then(keyword("not").or_not())
+            .then(select_token! {
+                Token::Keyword("empty") => "empty",
+                Token::Keyword("defined") => "defined",
+            })
+            .map(|(not, test)| match (not.is_some(), test) {
+                (false, "empty") => UnaryOperator::IsEmpty,
+                (true, "empty") => UnaryOperator::IsNotEmpty,
+                (false, _) => UnaryOperator::IsDefined,
+                (true, _) => UnaryOperator::IsNotDefined,
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::{test_parser, Expect, UnaryOperator};
+
+    #[test]
+    fn test_parse() {
+        test_parser("+", UnaryOperator::Plus);
+        test_parser("-", UnaryOperator::Minus);
+        test_parser("!", UnaryOperator::Not);
+        test_parser("not", UnaryOperator::Not);
+        test_parser("is empty", UnaryOperator::IsEmpty);
+        test_parser("is not empty", UnaryOperator::IsNotEmpty);
+        test_parser("is defined", UnaryOperator::IsDefined);
+        test_parser("is  not\tdefined", UnaryOperator::IsNotDefined);
+    }
+
+    impl From<UnaryOperator> for Expect<UnaryOperator> {
+        fn from(value: UnaryOperator) -> Self {
+            Expect::Something(value)
+        }
+    }
 }
@@ -0,0 +1,33 @@
+use crate::runtime::{mismatch, RuntimeError, Value};
+
+/// A function provided by the runtime rather than defined in a policy
+pub(crate) type Builtin = fn(Vec<Value>) -> Result<Value, RuntimeError>;
+
+/// Looks up a builtin function by name. A variable with the same name shadows the
+/// builtin.
+pub(crate) fn builtin(name: &str) -> Option<Builtin> {
+    match name {
+        "length" => Some(length),
+        _ => None,
+    }
+}
+
+/// `length(value)`: the number of items in a list or map, or the number of Unicode
+/// scalar values in a string
+fn length(args: Vec<Value>) -> Result<Value, RuntimeError> {
+    let [value] = <[Value; 1]>::try_from(args)
+        .map_err(|args| mismatch(format!("`length` expects 1 argument, got {}", args.len())))?;
+    let len = match value {
+        Value::Undefined => return Ok(Value::Undefined),
+        Value::String(s) => s.chars().count(),
+        Value::List(items) => items.len(),
+        Value::Map(entries) => entries.len(),
+        other => {
+            return Err(mismatch(format!(
+                "cannot take the length of {}",
+                other.type_name()
+            )))
+        }
+    };
+    Ok(Value::Integer(len as i64))
+}
@@ -0,0 +1,41 @@
+use std::fmt::{Display, Formatter};
+
+/// An error raised while evaluating a policy
+#[derive(Debug, PartialEq, Clone)]
+pub enum RuntimeError {
+    /// A variable was referenced that is not defined in the current scope
+    UndefinedVariable(String),
+    /// A parameter without a default value was not supplied by the host
+    MissingParameter(String),
+    /// A policy imports something the host did not supply
+    MissingImport(String),
+    /// An operation was applied to a value of the wrong type
+    TypeMismatch(String),
+    DivisionByZero,
+    IntegerOverflow,
+    /// The expression is valid syntax but cannot be evaluated yet
+    Unsupported(String),
+    /// Part of the tree failed to parse and was only kept for error recovery
+    InvalidSyntax,
+}
+
+impl Display for RuntimeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RuntimeError::UndefinedVariable(name) => write!(f, "undefined variable `{}`", name),
+            RuntimeError::MissingParameter(name) => {
+                write!(f, "missing value for required parameter `{}`", name)
+            }
+            RuntimeError::MissingImport(name) => {
+                write!(f, "no value supplied for import \"{}\"", name)
+            }
+            RuntimeError::TypeMismatch(message) => write!(f, "type mismatch: {}", message),
+            RuntimeError::DivisionByZero => write!(f, "division by zero"),
+            RuntimeError::IntegerOverflow => write!(f, "integer overflow"),
+            RuntimeError::Unsupported(what) => write!(f, "unsupported: {}", what),
+            RuntimeError::InvalidSyntax => write!(f, "cannot evaluate invalid syntax"),
+        }
+    }
+}
+
+impl std::error::Error for RuntimeError {}
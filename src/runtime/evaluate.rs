@@ -0,0 +1,659 @@
+use crate::parser::{
+    BinaryOperator, Expression, Identifier, Literal, Rule, Spanned, StringPart, UnaryOperator,
+};
+use crate::runtime::{builtin, Function, RuntimeError, Scope, Value};
+use std::cmp::Ordering;
+use std::sync::Arc;
+
+pub(crate) trait Evaluate {
+    fn evaluate(&self, scope: &Scope) -> Result<Value, RuntimeError>;
+}
+
+impl Evaluate for Literal {
+    fn evaluate(&self, _: &Scope) -> Result<Value, RuntimeError> {
+        Ok(self.clone().into())
+    }
+}
+
+impl Evaluate for Expression {
+    fn evaluate(&self, scope: &Scope) -> Result<Value, RuntimeError> {
+        match self {
+            Expression::Literal(literal) => literal.evaluate(scope),
+            Expression::Identifier(identifier) => scope
+                .get(&identifier.0)
+                .ok_or_else(|| RuntimeError::UndefinedVariable(identifier.0.clone())),
+            Expression::UnaryExpr { op, expr } => unary(op, expr.evaluate(scope)?),
+            Expression::BinaryExpr { left, op, right } => binary(left, op, right, scope),
+            Expression::Call { func, args } => {
+                let args = args
+                    .iter()
+                    .map(|arg| arg.evaluate(scope))
+                    .collect::<Result<Vec<_>, _>>()?;
+                if let Expression::Identifier(name) = &func.node {
+                    if let Some(builtin) = builtin(&name.0).filter(|_| !scope.contains(&name.0)) {
+                        return builtin(args);
+                    }
+                }
+                match func.evaluate(scope)? {
+                    Value::Function(function) => function.call(args, scope),
+                    other => Err(mismatch(format!("cannot call {}", other.type_name()))),
+                }
+            }
+            Expression::Function { params, body } => Ok(Value::Function(Arc::new(Function {
+                params: params.iter().map(|param| param.node.clone()).collect(),
+                body: body.node.clone(),
+                closure: scope.frame(),
+            }))),
+            Expression::Index { collection, index } => {
+                let index = index.evaluate(scope)?;
+                match collection.evaluate(scope)? {
+                    Value::Undefined => Ok(Value::Undefined),
+                    Value::List(items) => match index {
+                        Value::Integer(i) => Ok(usize::try_from(i)
+                            .ok()
+                            .and_then(|i| items.get(i).cloned())
+                            .unwrap_or(Value::Undefined)),
+                        other => Err(mismatch(format!(
+                            "cannot index list with {}",
+                            other.type_name()
+                        ))),
+                    },
+                    map @ Value::Map(_) => Ok(map.get(&index).cloned().unwrap_or(Value::Undefined)),
+                    Value::String(s) => match index {
+                        Value::Integer(i) => Ok(usize::try_from(i)
+                            .ok()
+                            .and_then(|i| s.chars().nth(i))
+                            .map_or(Value::Undefined, Value::string)),
+                        other => Err(mismatch(format!(
+                            "cannot index string with {}",
+                            other.type_name()
+                        ))),
+                    },
+                    other => Err(mismatch(format!("cannot index {}", other.type_name()))),
+                }
+            }
+            Expression::Slice {
+                collection,
+                start,
+                end,
+            } => {
+                let collection = collection.evaluate(scope)?;
+                let start = bound(start.as_deref().map(|start| &start.node), scope)?;
+                let end = bound(end.as_deref().map(|end| &end.node), scope)?;
+                match collection {
+                    Value::Undefined => Ok(Value::Undefined),
+                    Value::List(items) => {
+                        let (start, end) = clamp(start, end, items.len());
+                        Ok(Value::List(items[start..end].to_vec()))
+                    }
+                    Value::String(s) => {
+                        let (start, end) = clamp(start, end, s.chars().count());
+                        Ok(Value::string(
+                            s.chars().skip(start).take(end - start).collect::<String>(),
+                        ))
+                    }
+                    other => Err(mismatch(format!("cannot slice {}", other.type_name()))),
+                }
+            }
+            Expression::Select { object, field } => match object.evaluate(scope)? {
+                Value::Undefined => Ok(Value::Undefined),
+                map @ Value::Map(_) => Ok(map
+                    .get(&Value::string(field.0.as_str()))
+                    .cloned()
+                    .unwrap_or(Value::Undefined)),
+                other => Err(mismatch(format!(
+                    "cannot select `{}` from {}",
+                    field.0,
+                    other.type_name()
+                ))),
+            },
+            Expression::List(items) => items
+                .iter()
+                .map(|item| item.evaluate(scope))
+                .collect::<Result<Vec<_>, _>>()
+                .map(Value::List),
+            Expression::Map(entries) => {
+                let mut map: Vec<(Value, Value)> = Vec::with_capacity(entries.len());
+                for (key, value) in entries {
+                    let key = key.evaluate(scope)?;
+                    let value = value.evaluate(scope)?;
+                    match map.iter_mut().find(|(k, _)| equals(k, &key)) {
+                        Some(entry) => entry.1 = value,
+                        None => map.push((key, value)),
+                    }
+                }
+                Ok(Value::Map(map))
+            }
+            Expression::Rule { when, body } => rule(when.as_deref(), body, scope),
+            Expression::Quantifier { .. } => Err(RuntimeError::Unsupported(
+                "quantifier expressions".to_string(),
+            )),
+            // An undefined value makes the whole string undefined, as with operators
+            Expression::Interpolation(parts) => {
+                let mut text = String::new();
+                for part in parts {
+                    match part {
+                        StringPart::Text(part) => text.push_str(part),
+                        StringPart::Expression(expr) => match expr.evaluate(scope)? {
+                            Value::Undefined => return Ok(Value::Undefined),
+                            value => text.push_str(&value.to_string()),
+                        },
+                    }
+                }
+                Ok(Value::string(text))
+            }
+            Expression::Error => Err(RuntimeError::InvalidSyntax),
+        }
+    }
+}
+
+pub(crate) fn mismatch<T: Into<String>>(message: T) -> RuntimeError {
+    RuntimeError::TypeMismatch(message.into())
+}
+
+/// Whether a collection is a map, and its key/value pairs
+pub(crate) type Elements = (bool, Vec<(Value, Value)>);
+
+/// Returns the key/value pairs of a collection and whether it is a map, or `None` if
+/// the collection is undefined. List elements are keyed by their index.
+pub(crate) fn elements(collection: Value) -> Result<Option<Elements>, RuntimeError> {
+    match collection {
+        Value::Undefined => Ok(None),
+        Value::List(items) => Ok(Some((
+            false,
+            items
+                .into_iter()
+                .enumerate()
+                .map(|(i, v)| (Value::Integer(i as i64), v))
+                .collect(),
+        ))),
+        Value::Map(entries) => Ok(Some((true, entries))),
+        other => Err(mismatch(format!(
+            "cannot iterate over {}",
+            other.type_name()
+        ))),
+    }
+}
+
+/// The variables a `for` loop binds for one element of a collection.
+/// With a single binding, lists bind their values and maps their keys.
+pub(crate) fn bindings<'a>(
+    key: Option<&'a Identifier>,
+    value: &'a Identifier,
+    is_map: bool,
+    k: Value,
+    v: Value,
+) -> Vec<(&'a str, Value)> {
+    match key {
+        Some(key) => vec![(&key.0, k), (&value.0, v)],
+        None if is_map => vec![(&value.0, k)],
+        None => vec![(&value.0, v)],
+    }
+}
+
+/// Evaluates an optional slice bound to an integer
+fn bound(expr: Option<&Expression>, scope: &Scope) -> Result<Option<i64>, RuntimeError> {
+    match expr.map(|expr| expr.evaluate(scope)).transpose()? {
+        None => Ok(None),
+        Some(Value::Integer(i)) => Ok(Some(i)),
+        Some(other) => Err(mismatch(format!(
+            "slice bounds must be int, found {}",
+            other.type_name()
+        ))),
+    }
+}
+
+impl Evaluate for Rule {
+    fn evaluate(&self, scope: &Scope) -> Result<Value, RuntimeError> {
+        rule(self.when.as_ref(), &self.body, scope)
+    }
+}
+
+fn rule(
+    when: Option<&Spanned<Expression>>,
+    body: &Expression,
+    scope: &Scope,
+) -> Result<Value, RuntimeError> {
+    // A rule whose guard is false is considered to pass
+    if let Some(when) = when {
+        match when.evaluate(scope)? {
+            Value::Boolean(true) => {}
+            Value::Boolean(false) => return Ok(Value::Boolean(true)),
+            other => {
+                return Err(mismatch(format!(
+                    "rule guard must be a bool, found {}",
+                    other.type_name()
+                )))
+            }
+        }
+    }
+    body.evaluate(scope)
+}
+
+/// Clamps optional slice bounds to the length of the collection being sliced
+pub(crate) fn clamp(start: Option<i64>, end: Option<i64>, len: usize) -> (usize, usize) {
+    let clamp = |i: i64| i.clamp(0, len as i64) as usize;
+    let start = start.map(clamp).unwrap_or(0);
+    let end = end.map(clamp).unwrap_or(len);
+    (start, end.max(start))
+}
+
+fn unary(op: &UnaryOperator, value: Value) -> Result<Value, RuntimeError> {
+    match (op, value) {
+        (UnaryOperator::IsDefined, value) => Ok(Value::Boolean(value != Value::Undefined)),
+        (UnaryOperator::IsNotDefined, value) => Ok(Value::Boolean(value == Value::Undefined)),
+        (_, Value::Undefined) => Ok(Value::Undefined),
+        (UnaryOperator::Plus, value @ (Value::Integer(_) | Value::Float(_))) => Ok(value),
+        (UnaryOperator::Minus, Value::Integer(i)) => i
+            .checked_neg()
+            .map(Value::Integer)
+            .ok_or(RuntimeError::IntegerOverflow),
+        (UnaryOperator::Minus, Value::Float(f)) => Ok(Value::Float(-f)),
+        (UnaryOperator::Not, Value::Boolean(b)) => Ok(Value::Boolean(!b)),
+        (UnaryOperator::IsEmpty, value) => is_empty(&value).map(Value::Boolean),
+        (UnaryOperator::IsNotEmpty, value) => is_empty(&value).map(|empty| Value::Boolean(!empty)),
+        (op, value) => Err(mismatch(format!(
+            "cannot apply {:?} to {}",
+            op,
+            value.type_name()
+        ))),
+    }
+}
+
+fn is_empty(value: &Value) -> Result<bool, RuntimeError> {
+    match value {
+        Value::String(s) => Ok(s.is_empty()),
+        Value::List(items) => Ok(items.is_empty()),
+        Value::Map(entries) => Ok(entries.is_empty()),
+        other => Err(mismatch(format!(
+            "{} has no notion of emptiness",
+            other.type_name()
+        ))),
+    }
+}
+
+fn binary(
+    left: &Expression,
+    op: &BinaryOperator,
+    right: &Expression,
+    scope: &Scope,
+) -> Result<Value, RuntimeError> {
+    // The fallback is only evaluated if it is needed
+    if *op == BinaryOperator::Else {
+        return match left.evaluate(scope)? {
+            Value::Undefined => right.evaluate(scope),
+            value => Ok(value),
+        };
+    }
+
+    // Logical operators short-circuit, so the right hand side is only evaluated if needed
+    if let BinaryOperator::And | BinaryOperator::Or = op {
+        return match (op, left.evaluate(scope)?) {
+            (_, Value::Undefined) => Ok(Value::Undefined),
+            (BinaryOperator::And, Value::Boolean(false)) => Ok(Value::Boolean(false)),
+            (BinaryOperator::Or, Value::Boolean(true)) => Ok(Value::Boolean(true)),
+            (_, Value::Boolean(_)) => match right.evaluate(scope)? {
+                value @ (Value::Boolean(_) | Value::Undefined) => Ok(value),
+                other => Err(mismatch(format!(
+                    "cannot apply {:?} to {}",
+                    op,
+                    other.type_name()
+                ))),
+            },
+            (_, other) => Err(mismatch(format!(
+                "cannot apply {:?} to {}",
+                op,
+                other.type_name()
+            ))),
+        };
+    }
+
+    apply(op, left.evaluate(scope)?, right.evaluate(scope)?)
+}
+
+/// Applies a binary operator that does not short-circuit to its evaluated operands
+pub(crate) fn apply(op: &BinaryOperator, lhs: Value, rhs: Value) -> Result<Value, RuntimeError> {
+    if lhs == Value::Undefined || rhs == Value::Undefined {
+        return Ok(Value::Undefined);
+    }
+
+    match op {
+        BinaryOperator::Add
+        | BinaryOperator::Subtract
+        | BinaryOperator::Multiply
+        | BinaryOperator::Divide
+        | BinaryOperator::Modulus => arithmetic(op, lhs, rhs),
+        BinaryOperator::Equals | BinaryOperator::Is => Ok(Value::Boolean(equals(&lhs, &rhs))),
+        BinaryOperator::NotEquals | BinaryOperator::IsNot => {
+            Ok(Value::Boolean(!equals(&lhs, &rhs)))
+        }
+        BinaryOperator::LessThan
+        | BinaryOperator::GreaterThan
+        | BinaryOperator::LessThanOrEqual
+        | BinaryOperator::GreaterThanOrEqual => {
+            let ordering = compare(&lhs, &rhs)?;
+            Ok(Value::Boolean(match op {
+                BinaryOperator::LessThan => ordering == Ordering::Less,
+                BinaryOperator::GreaterThan => ordering == Ordering::Greater,
+                BinaryOperator::LessThanOrEqual => ordering != Ordering::Greater,
+                _ => ordering != Ordering::Less,
+            }))
+        }
+        BinaryOperator::Xor => match (lhs, rhs) {
+            (Value::Boolean(a), Value::Boolean(b)) => Ok(Value::Boolean(a ^ b)),
+            (a, b) => Err(mismatch(format!(
+                "cannot apply Xor to {} and {}",
+                a.type_name(),
+                b.type_name()
+            ))),
+        },
+        BinaryOperator::Contains => contains(&lhs, &rhs).map(Value::Boolean),
+        BinaryOperator::In => contains(&rhs, &lhs).map(Value::Boolean),
+        BinaryOperator::NotContains => contains(&lhs, &rhs).map(|found| Value::Boolean(!found)),
+        BinaryOperator::NotIn => contains(&rhs, &lhs).map(|found| Value::Boolean(!found)),
+        BinaryOperator::Matches | BinaryOperator::NotMatches => Err(RuntimeError::Unsupported(
+            "regular expression matching".to_string(),
+        )),
+        BinaryOperator::And | BinaryOperator::Or | BinaryOperator::Else => {
+            unreachable!("handled above")
+        }
+    }
+}
+
+fn arithmetic(op: &BinaryOperator, lhs: Value, rhs: Value) -> Result<Value, RuntimeError> {
+    match (lhs, rhs) {
+        (Value::Integer(a), Value::Integer(b)) => {
+            if b == 0 && matches!(op, BinaryOperator::Divide | BinaryOperator::Modulus) {
+                return Err(RuntimeError::DivisionByZero);
+            }
+            match op {
+                BinaryOperator::Add => a.checked_add(b),
+                BinaryOperator::Subtract => a.checked_sub(b),
+                BinaryOperator::Multiply => a.checked_mul(b),
+                BinaryOperator::Divide => a.checked_div(b),
+                _ => a.checked_rem(b),
+            }
+            .map(Value::Integer)
+            .ok_or(RuntimeError::IntegerOverflow)
+        }
+        (Value::String(a), Value::String(b)) if *op == BinaryOperator::Add => {
+            Ok(Value::string(format!("{}{}", a, b)))
+        }
+        (Value::List(mut a), Value::List(b)) if *op == BinaryOperator::Add => {
+            a.extend(b);
+            Ok(Value::List(a))
+        }
+        (lhs, rhs) => match (as_float(&lhs), as_float(&rhs)) {
+            (Some(a), Some(b)) => {
+                if b == 0.0 && matches!(op, BinaryOperator::Divide | BinaryOperator::Modulus) {
+                    return Err(RuntimeError::DivisionByZero);
+                }
+                Ok(Value::Float(match op {
+                    BinaryOperator::Add => a + b,
+                    BinaryOperator::Subtract => a - b,
+                    BinaryOperator::Multiply => a * b,
+                    BinaryOperator::Divide => a / b,
+                    _ => a % b,
+                }))
+            }
+            _ => Err(mismatch(format!(
+                "cannot apply {:?} to {} and {}",
+                op,
+                lhs.type_name(),
+                rhs.type_name()
+            ))),
+        },
+    }
+}
+
+fn as_float(value: &Value) -> Option<f64> {
+    match value {
+        Value::Integer(i) => Some(*i as f64),
+        Value::Float(f) => Some(*f),
+        _ => None,
+    }
+}
+
+/// Equality where integers and floats compare by numeric value
+pub(crate) fn equals(lhs: &Value, rhs: &Value) -> bool {
+    match (lhs, rhs) {
+        (Value::Integer(_), Value::Float(_)) | (Value::Float(_), Value::Integer(_)) => {
+            as_float(lhs) == as_float(rhs)
+        }
+        _ => lhs == rhs,
+    }
+}
+
+fn compare(lhs: &Value, rhs: &Value) -> Result<Ordering, RuntimeError> {
+    let ordering = match (lhs, rhs) {
+        (Value::Integer(a), Value::Integer(b)) => Some(a.cmp(b)),
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        _ => match (as_float(lhs), as_float(rhs)) {
+            (Some(a), Some(b)) => a.partial_cmp(&b),
+            _ => None,
+        },
+    };
+    ordering.ok_or_else(|| {
+        mismatch(format!(
+            "cannot compare {} and {}",
+            lhs.type_name(),
+            rhs.type_name()
+        ))
+    })
+}
+
+fn contains(collection: &Value, item: &Value) -> Result<bool, RuntimeError> {
+    match (collection, item) {
+        (Value::List(items), item) => Ok(items.iter().any(|v| equals(v, item))),
+        (Value::Map(entries), key) => Ok(entries.iter().any(|(k, _)| equals(k, key))),
+        (Value::String(s), Value::String(sub)) => Ok(s.contains(sub.as_str())),
+        (collection, item) => Err(mismatch(format!(
+            "{} cannot contain {}",
+            collection.type_name(),
+            item.type_name()
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{parse, FileId};
+
+    fn eval(input: &str) -> Result<Value, RuntimeError> {
+        let scope = Scope::new().with("list", Value::from(vec![1, 2, 3])).with(
+            "plan",
+            Value::Map(vec![(
+                Value::from("resources"),
+                Value::from(vec![Value::Map(vec![(
+                    Value::from("type"),
+                    Value::from("aws_instance"),
+                )])]),
+            )]),
+        );
+        let (expr, errors) = parse::<Expression>(input, FileId::default());
+        assert!(errors.is_empty(), "expression should parse: {:?}", errors);
+        expr.unwrap().evaluate(&scope)
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        assert_eq!(eval("1 + 2 * 3"), Ok(Value::Integer(7)));
+        assert_eq!(eval("7 % 4 - 1"), Ok(Value::Integer(2)));
+        assert_eq!(eval("1 + 0.5"), Ok(Value::Float(1.5)));
+        assert_eq!(eval(r#""a" + "b""#), Ok(Value::from("ab")));
+        assert_eq!(eval("[1] + [2]"), Ok(Value::from(vec![1, 2])));
+        assert_eq!(eval("1 / 0"), Err(RuntimeError::DivisionByZero));
+        assert_eq!(
+            eval("9223372036854775807 + 1"),
+            Err(RuntimeError::IntegerOverflow)
+        );
+    }
+
+    #[test]
+    fn test_comparison_and_logic() {
+        assert_eq!(eval("1 == 1.0"), Ok(Value::Boolean(true)));
+        assert_eq!(eval(r#""a" < "b" and 2 >= 3"#), Ok(Value::Boolean(false)));
+        assert_eq!(eval("true or missing"), Ok(Value::Boolean(true)));
+        assert_eq!(
+            eval("false or missing"),
+            Err(RuntimeError::UndefinedVariable("missing".to_string()))
+        );
+        assert_eq!(
+            eval("2 in list and list contains 3"),
+            Ok(Value::Boolean(true))
+        );
+    }
+
+    #[test]
+    fn test_numeric_map_keys() {
+        // Map keys compare like `==` and `in`, so ints and floats of equal value match
+        assert_eq!(eval(r#"{1: "a"}[1.0]"#), Ok(Value::from("a")));
+        assert_eq!(eval(r#"{1.0: "a"}[1]"#), Ok(Value::from("a")));
+        assert_eq!(eval(r#"1.0 in {1: "a"}"#), Ok(Value::Boolean(true)));
+        assert_eq!(
+            eval(r#"{1: "a", 1.0: "b"}"#),
+            Ok(Value::Map(vec![(Value::Integer(1), Value::from("b"))]))
+        );
+    }
+
+    #[test]
+    fn test_negated_operators() {
+        assert_eq!(eval("4 not in list"), Ok(Value::Boolean(true)));
+        assert_eq!(eval("list not contains 2"), Ok(Value::Boolean(false)));
+        assert_eq!(eval("not (2 in list)"), Ok(Value::Boolean(false)));
+        assert_eq!(eval("plan.missing not in list"), Ok(Value::Undefined));
+    }
+
+    #[test]
+    fn test_else() {
+        assert_eq!(eval("plan.missing.deeper else 5"), Ok(Value::Integer(5)));
+        assert_eq!(eval("list[10] else list[0]"), Ok(Value::Integer(1)));
+        assert_eq!(eval("null else 1"), Ok(Value::Null));
+        assert_eq!(eval("false else true"), Ok(Value::Boolean(false)));
+        assert_eq!(eval("1 else missing"), Ok(Value::Integer(1)));
+        assert_eq!(
+            eval("plan.missing or true else false"),
+            Ok(Value::Boolean(false))
+        );
+        assert_eq!(eval("plan.missing else 1 + 1"), Ok(Value::Integer(2)));
+    }
+
+    #[test]
+    fn test_unicode_strings() {
+        assert_eq!(eval(r#""née"[1:2]"#), Ok(Value::from("é")));
+        assert_eq!(
+            eval(r#""日本語テキスト"[2:]"#),
+            Ok(Value::from("語テキスト"))
+        );
+        assert_eq!(eval(r#""a😀b"[:2]"#), Ok(Value::from("a😀")));
+        assert_eq!(eval(r#""née"[5:10]"#), Ok(Value::from("")));
+        assert_eq!(eval(r#""née"[1]"#), Ok(Value::from("é")));
+        assert_eq!(eval(r#""née"[3]"#), Ok(Value::Undefined));
+        assert_eq!(
+            eval(r#""née"["a"]"#),
+            Err(mismatch("cannot index string with string"))
+        );
+        assert_eq!(eval(r#"length("née")"#), Ok(Value::Integer(3)));
+        assert_eq!(eval(r#"length("a😀b")"#), Ok(Value::Integer(3)));
+        assert_eq!(eval(r#"length("")"#), Ok(Value::Integer(0)));
+    }
+
+    #[test]
+    fn test_length() {
+        assert_eq!(eval("length(list)"), Ok(Value::Integer(3)));
+        assert_eq!(eval(r#"length({"a": 1})"#), Ok(Value::Integer(1)));
+        assert_eq!(eval("length(plan.missing)"), Ok(Value::Undefined));
+        assert_eq!(
+            eval("length(1)"),
+            Err(mismatch("cannot take the length of int"))
+        );
+        assert_eq!(
+            eval("length(list, list)"),
+            Err(mismatch("`length` expects 1 argument, got 2"))
+        );
+    }
+
+    #[test]
+    fn test_interpolation() {
+        assert_eq!(
+            eval(r#""type ${plan.resources[0].type} x${1 + 1}""#),
+            Ok(Value::from("type aws_instance x2"))
+        );
+        assert_eq!(
+            eval(r#""${1.5} ${2.0} ${null} ${true} ${list} ${ {"a": ["b"]} }""#),
+            Ok(Value::from(r#"1.5 2.0 null true [1, 2, 3] {"a": ["b"]}"#))
+        );
+        assert_eq!(
+            eval(r#""outer ${"inner ${list[0]}"}""#),
+            Ok(Value::from("outer inner 1"))
+        );
+        assert_eq!(eval(r#""cost \${x}""#), Ok(Value::from("cost ${x}")));
+        assert_eq!(eval(r#""a ${plan.missing} b""#), Ok(Value::Undefined));
+        assert_eq!(
+            eval(r#""a ${missing}""#),
+            Err(RuntimeError::UndefinedVariable("missing".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_collections() {
+        assert_eq!(eval("list[1]"), Ok(Value::Integer(2)));
+        assert_eq!(eval("list[10]"), Ok(Value::Undefined));
+        assert_eq!(eval("list[1:]"), Ok(Value::from(vec![2, 3])));
+        assert_eq!(eval("list[:10]"), Ok(Value::from(vec![1, 2, 3])));
+        assert_eq!(
+            eval("plan.resources[0].type"),
+            Ok(Value::from("aws_instance"))
+        );
+        assert_eq!(eval("plan.missing.deeper[0]"), Ok(Value::Undefined));
+        assert_eq!(eval("list is not empty"), Ok(Value::Boolean(true)));
+        assert_eq!(eval("list[:0] is empty"), Ok(Value::Boolean(true)));
+        assert_eq!(eval("plan.missing is defined"), Ok(Value::Boolean(false)));
+        assert_eq!(
+            eval(r#"{"a": 1, "a": 2}"#),
+            Ok(Value::Map(vec![(Value::from("a"), Value::Integer(2))]))
+        );
+    }
+
+    #[test]
+    fn test_unsupported() {
+        assert_eq!(
+            eval("all list as v { v > 0 }"),
+            Err(RuntimeError::Unsupported(
+                "quantifier expressions".to_string()
+            ))
+        );
+        assert_eq!(
+            eval(r#""abc" matches "a.c""#),
+            Err(RuntimeError::Unsupported(
+                "regular expression matching".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_functions() {
+        assert_eq!(
+            eval("func(a, b) { return a + b }(1, 2)"),
+            Ok(Value::Integer(3))
+        );
+        assert_eq!(
+            eval("func(f) { return f(2) }(func(x) { return x * x })"),
+            Ok(Value::Integer(4))
+        );
+        assert_eq!(
+            eval("func(a) { return a }()"),
+            Err(mismatch("function expects 1 argument(s), got 0"))
+        );
+        assert_eq!(eval("func() { x = 1 }()"), Ok(Value::Undefined));
+        assert_eq!(eval("list(1)"), Err(mismatch("cannot call list")));
+    }
+
+    #[test]
+    fn test_rules() {
+        assert_eq!(eval("rule { 1 < 2 }"), Ok(Value::Boolean(true)));
+        assert_eq!(eval("rule when false { 1 > 2 }"), Ok(Value::Boolean(true)));
+        assert_eq!(eval("rule when true { 1 > 2 }"), Ok(Value::Boolean(false)));
+    }
+}
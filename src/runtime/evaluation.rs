@@ -0,0 +1,60 @@
+use crate::parser::{Item, Policy, Statement};
+use crate::runtime::{mismatch, Evaluate, Execute, Flow, Parameters, RuntimeError, Scope, Value};
+
+/// The outcome of evaluating a policy
+#[derive(Debug, PartialEq, Clone)]
+pub struct Evaluation {
+    /// The value of every rule, in the order they were declared
+    pub rules: Vec<(String, Value)>,
+    /// The global variables once every statement has run, including parameters
+    pub globals: Scope,
+}
+
+impl Evaluation {
+    /// The value of the named rule
+    pub fn rule(&self, name: &str) -> Option<&Value> {
+        self.rules
+            .iter()
+            .find(|(rule, _)| rule == name)
+            .map(|(_, value)| value)
+    }
+
+    /// Whether the policy passed, which is the value of its `main` rule
+    pub fn passed(&self) -> Option<bool> {
+        match self.rule("main") {
+            Some(Value::Boolean(passed)) => Some(*passed),
+            _ => None,
+        }
+    }
+}
+
+impl Policy {
+    /// Evaluates the policy with the imports and parameter values supplied by the host.
+    /// Statements and rules run in source order, and each rule's value is bound to its
+    /// name so later rules and statements can refer to it.
+    pub fn evaluate(&self, params: &Parameters) -> Result<Evaluation, RuntimeError> {
+        let mut globals = params.bind(&self.imports, &self.params)?;
+        let mut rules = vec![];
+        for item in &self.items {
+            match item {
+                Item::Statement(statement) => execute_global(statement, &mut globals)?,
+                Item::Rule(rule) => {
+                    let value = rule.evaluate(&globals)?;
+                    globals.set(rule.name.0.clone(), value.clone());
+                    rules.push((rule.name.0.clone(), value));
+                }
+            }
+        }
+        Ok(Evaluation { rules, globals })
+    }
+}
+
+/// Runs a top-level statement, which cannot transfer control anywhere
+fn execute_global(statement: &Statement, scope: &mut Scope) -> Result<(), RuntimeError> {
+    match statement.execute(scope)? {
+        Flow::Next => Ok(()),
+        _ => Err(mismatch(
+            "`break`, `continue` and `return` are not allowed at the top level of a policy",
+        )),
+    }
+}
@@ -0,0 +1,536 @@
+use crate::parser::{Expression, Identifier, Statement};
+use crate::runtime::{
+    apply, bindings, elements, equals, mismatch, Evaluate, Function, RuntimeError, Scope, Value,
+};
+
+/// How control leaves a statement once it has been executed
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) enum Flow {
+    Next,
+    Break,
+    Continue,
+    Return(Value),
+}
+
+pub(crate) trait Execute {
+    fn execute(&self, scope: &mut Scope) -> Result<Flow, RuntimeError>;
+}
+
+impl Execute for Statement {
+    fn execute(&self, scope: &mut Scope) -> Result<Flow, RuntimeError> {
+        match self {
+            Statement::Expression(expr) => expr.evaluate(scope).map(|_| Flow::Next),
+            Statement::Assignment { target, op, value } => {
+                let mut value = value.evaluate(scope)?;
+                // Updating a missing variable, key or field is an error rather than a
+                // silent `undefined`
+                if let Some(op) = op {
+                    let current = target.evaluate(scope)?;
+                    if current == Value::Undefined {
+                        return Err(mismatch("cannot update an undefined value"));
+                    }
+                    value = apply(op, current, value)?;
+                }
+                assign(&target.node, value, scope)?;
+                Ok(Flow::Next)
+            }
+            Statement::Block(statements) => {
+                for statement in statements {
+                    match statement.execute(scope)? {
+                        Flow::Next => {}
+                        flow => return Ok(flow),
+                    }
+                }
+                Ok(Flow::Next)
+            }
+            Statement::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => match condition.evaluate(scope)? {
+                Value::Boolean(true) => then_branch.execute(scope),
+                Value::Boolean(false) => match else_branch {
+                    Some(else_branch) => else_branch.execute(scope),
+                    None => Ok(Flow::Next),
+                },
+                other => Err(mismatch(format!(
+                    "if condition must be a bool, found {}",
+                    other.type_name()
+                ))),
+            },
+            Statement::Case {
+                expr,
+                clauses,
+                else_clause,
+            } => {
+                // Without an expression each `when` clause is a condition of its own
+                let subject = match expr {
+                    Some(expr) => expr.evaluate(scope)?,
+                    None => Value::Boolean(true),
+                };
+                for (candidates, body) in clauses {
+                    for candidate in candidates {
+                        if equals(&subject, &candidate.evaluate(scope)?) {
+                            return body.execute(scope);
+                        }
+                    }
+                }
+                match else_clause {
+                    Some(else_clause) => else_clause.execute(scope),
+                    None => Ok(Flow::Next),
+                }
+            }
+            Statement::For {
+                collection,
+                key,
+                value,
+                body,
+            } => {
+                let (is_map, elements) = match elements(collection.evaluate(scope)?)? {
+                    Some(elements) => elements,
+                    None => return Ok(Flow::Next),
+                };
+                // The loop variables only exist within the loop, so any variables they
+                // shadow get their values back once it ends
+                let names = key.iter().chain([value]).map(|name| name.0.as_str());
+                let shadowed = names
+                    .map(|name| (name, scope.remove(name)))
+                    .collect::<Vec<_>>();
+                let flow = iterate(elements, key.as_deref(), value, is_map, body, scope);
+                for (name, previous) in shadowed {
+                    scope.remove(name);
+                    if let Some(previous) = previous {
+                        scope.set(name, previous);
+                    }
+                }
+                flow
+            }
+            Statement::Break => Ok(Flow::Break),
+            Statement::Continue => Ok(Flow::Continue),
+            Statement::Return(expr) => match expr {
+                Some(expr) => expr.evaluate(scope).map(Flow::Return),
+                None => Ok(Flow::Return(Value::Undefined)),
+            },
+            Statement::Error => Err(RuntimeError::InvalidSyntax),
+        }
+    }
+}
+
+impl Function {
+    /// Calls the function from `caller` with the given arguments. The body sees the
+    /// frame the function captured and the current globals. A function that
+    /// finishes without returning evaluates to `undefined`.
+    pub fn call(&self, args: Vec<Value>, caller: &Scope) -> Result<Value, RuntimeError> {
+        if args.len() != self.params.len() {
+            return Err(mismatch(format!(
+                "function expects {} argument(s), got {}",
+                self.params.len(),
+                args.len()
+            )));
+        }
+        let mut scope = caller.call(self.closure.clone());
+        for (param, arg) in self.params.iter().zip(args) {
+            scope.set(param.0.clone(), arg);
+        }
+        let result = match self.body.execute(&mut scope) {
+            Ok(Flow::Return(value)) => Ok(value),
+            Ok(Flow::Next) => Ok(Value::Undefined),
+            Ok(Flow::Break | Flow::Continue) => Err(mismatch(
+                "`break` and `continue` are only allowed inside a loop",
+            )),
+            Err(err) => Err(err),
+        };
+        if let Some(frame) = scope.frame() {
+            frame.release(result.as_ref().unwrap_or(&Value::Undefined));
+        }
+        result
+    }
+}
+
+/// Runs the body of a `for` loop once for each element, binding the loop variables
+/// in `scope`
+fn iterate(
+    elements: Vec<(Value, Value)>,
+    key: Option<&Identifier>,
+    value: &Identifier,
+    is_map: bool,
+    body: &Statement,
+    scope: &mut Scope,
+) -> Result<Flow, RuntimeError> {
+    for (k, v) in elements {
+        for (name, value) in bindings(key, value, is_map, k, v) {
+            scope.set(name, value);
+        }
+        match body.execute(scope)? {
+            Flow::Next | Flow::Continue => {}
+            Flow::Break => break,
+            flow @ Flow::Return(_) => return Ok(flow),
+        }
+    }
+    Ok(Flow::Next)
+}
+
+/// Stores a value in an assignment target. Assigning to an index or field replaces
+/// the whole collection it belongs to, since values are never shared.
+fn assign(target: &Expression, value: Value, scope: &mut Scope) -> Result<(), RuntimeError> {
+    match target {
+        Expression::Identifier(identifier) => {
+            scope.set(identifier.0.clone(), value);
+            Ok(())
+        }
+        Expression::Index { collection, index } => {
+            let key = index.evaluate(scope)?;
+            let updated = insert(collection.evaluate(scope)?, key, value)?;
+            assign(collection, updated, scope)
+        }
+        Expression::Select { object, field } => {
+            let key = Value::string(field.0.as_str());
+            let updated = insert(object.evaluate(scope)?, key, value)?;
+            assign(object, updated, scope)
+        }
+        _ => Err(RuntimeError::Unsupported(
+            "assignment to this kind of expression".to_string(),
+        )),
+    }
+}
+
+/// Sets an element of a list or map, adding the key if a map does not have it yet
+fn insert(collection: Value, key: Value, value: Value) -> Result<Value, RuntimeError> {
+    match collection {
+        Value::List(mut items) => match key {
+            Value::Integer(i) => {
+                let len = items.len();
+                let slot = usize::try_from(i)
+                    .ok()
+                    .and_then(|i| items.get_mut(i))
+                    .ok_or_else(|| {
+                        mismatch(format!(
+                            "list index {} is out of range for a list of length {}",
+                            i, len
+                        ))
+                    })?;
+                *slot = value;
+                Ok(Value::List(items))
+            }
+            other => Err(mismatch(format!(
+                "cannot index list with {}",
+                other.type_name()
+            ))),
+        },
+        Value::Map(mut entries) => {
+            match entries.iter_mut().find(|(k, _)| equals(k, &key)) {
+                Some(entry) => entry.1 = value,
+                None => entries.push((key, value)),
+            }
+            Ok(Value::Map(entries))
+        }
+        other => Err(mismatch(format!(
+            "cannot assign an element of {}",
+            other.type_name()
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_policy;
+    use std::sync::Arc;
+
+    fn run(input: &str) -> Result<Scope, RuntimeError> {
+        let policy = parse_policy(input).expect("policy should parse");
+        let mut scope = Scope::new();
+        for statement in policy.statements() {
+            statement.execute(&mut scope)?;
+        }
+        Ok(scope)
+    }
+
+    #[test]
+    fn test_control_flow() {
+        let scope = run(r#"
+            total = 0
+            for [1, 2, 3, 4, 5] as v {
+                if v == 2 { continue }
+                if v == 4 { break }
+                total = total + v
+            }
+            case total {
+                when 1, 2: size = "small"
+                when 4: size = "exact"
+                else: size = "large"
+            }
+        "#)
+        .unwrap();
+        assert_eq!(scope.get("total"), Some(Value::Integer(4)));
+        assert_eq!(scope.get("size"), Some(Value::from("exact")));
+    }
+
+    #[test]
+    fn test_loop_variables_do_not_leak() {
+        let scope = run(r#"
+            k = "outer"
+            for {"a": 1, "b": 2} as k, v {
+                last = k
+            }
+            for [1, 2] as i { }
+        "#)
+        .unwrap();
+        assert_eq!(scope.get("k"), Some(Value::from("outer")));
+        assert_eq!(scope.get("v"), None);
+        assert_eq!(scope.get("i"), None);
+        assert_eq!(scope.get("last"), Some(Value::from("b")));
+
+        // Inside a function, a loop variable shadowing a global leaves the global alone
+        let scope = run(r#"
+            x = 0
+            f = func() {
+                for [1, 2] as x { }
+                return x
+            }
+            y = f()
+        "#)
+        .unwrap();
+        assert_eq!(scope.get("y"), Some(Value::Integer(0)));
+    }
+
+    #[test]
+    fn test_compound_and_indexed_assignment() {
+        let scope = run(r#"
+            count = 10
+            count += 5
+            count -= 1
+            count *= 3
+            count /= 2
+            count %= 4
+            name = "a"
+            name += "b"
+            ports = [80, 443]
+            ports[1] = 8443
+            ports[0] += 1
+            tags = {"env": "dev"}
+            tags["env"] = "prod"
+            tags.owner = "ops"
+            nested = {"a": [1, {"b": 2}]}
+            nested["a"][1]["b"] += 40
+        "#)
+        .unwrap();
+        assert_eq!(scope.get("count"), Some(Value::Integer(1)));
+        assert_eq!(scope.get("name"), Some(Value::string("ab")));
+        assert_eq!(
+            scope.get("ports"),
+            Some(Value::List(vec![Value::Integer(81), Value::Integer(8443)]))
+        );
+        assert_eq!(
+            scope.get("tags"),
+            Some(Value::Map(vec![
+                (Value::string("env"), Value::string("prod")),
+                (Value::string("owner"), Value::string("ops")),
+            ]))
+        );
+        assert_eq!(
+            scope.get("nested"),
+            Some(Value::Map(vec![(
+                Value::string("a"),
+                Value::List(vec![
+                    Value::Integer(1),
+                    Value::Map(vec![(Value::string("b"), Value::Integer(42))]),
+                ]),
+            )]))
+        );
+    }
+
+    #[test]
+    fn test_assign_numeric_map_keys() {
+        let scope = run("m = {1: \"a\"}\nm[1.0] = \"b\"").unwrap();
+        assert_eq!(
+            scope.get("m"),
+            Some(Value::Map(vec![(Value::Integer(1), Value::from("b"))]))
+        );
+    }
+
+    #[test]
+    fn test_assignment_errors() {
+        assert_eq!(
+            run("total += 1").unwrap_err(),
+            RuntimeError::UndefinedVariable("total".to_string())
+        );
+        assert_eq!(
+            run("m = {}\nm[\"k\"] += 1").unwrap_err(),
+            mismatch("cannot update an undefined value")
+        );
+        assert_eq!(
+            run("m = {}\nm.count -= 1").unwrap_err(),
+            mismatch("cannot update an undefined value")
+        );
+        assert_eq!(
+            run("xs = [1]\nxs[3] *= 2").unwrap_err(),
+            mismatch("cannot update an undefined value")
+        );
+        assert_eq!(
+            run("xs = [1]\nxs[1] = 2").unwrap_err(),
+            mismatch("list index 1 is out of range for a list of length 1")
+        );
+        assert_eq!(
+            run("xs = [1]\nxs[\"a\"] = 2").unwrap_err(),
+            mismatch("cannot index list with string")
+        );
+        assert_eq!(
+            run("n = 1\nn[0] = 2").unwrap_err(),
+            mismatch("cannot assign an element of int")
+        );
+    }
+
+    #[test]
+    fn test_recursive_functions() {
+        let scope = run(r#"
+            fact = func(n) {
+                if n <= 1 { return 1 }
+                return n * fact(n - 1)
+            }
+            result = fact(5)
+        "#)
+        .unwrap();
+        assert_eq!(scope.get("result"), Some(Value::Integer(120)));
+    }
+
+    #[test]
+    fn test_nested_recursive_functions() {
+        let scope = run(r#"
+            outer = func() {
+                fact = func(n) {
+                    if n <= 1 { return 1 }
+                    return n * fact(n - 1)
+                }
+                return fact(5)
+            }
+            result = outer()
+        "#)
+        .unwrap();
+        assert_eq!(scope.get("result"), Some(Value::Integer(120)));
+    }
+
+    #[test]
+    fn test_calls_release_their_frames() {
+        // Define `outer` in a frame of its own, which each of its calls holds as a parent
+        let mut scope = Scope::new().call(None);
+        let policy = parse_policy(
+            r#"
+            outer = func() {
+                fact = func(n) {
+                    if n <= 1 { return 1 }
+                    return n * fact(n - 1)
+                }
+                return fact(5)
+            }
+            make = func() {
+                f = func() { return f }
+                return f
+            }
+            "#,
+        )
+        .unwrap();
+        for statement in policy.statements() {
+            statement.execute(&mut scope).unwrap();
+        }
+        let frame = scope.frame().unwrap();
+        let count = Arc::strong_count(&frame);
+        let Some(Value::Function(outer)) = scope.get("outer") else {
+            panic!("outer should be a function")
+        };
+        assert_eq!(outer.call(vec![], &scope), Ok(Value::Integer(120)));
+        assert_eq!(Arc::strong_count(&frame), count);
+
+        // A returned function keeps the frame of its call alive
+        let Some(Value::Function(make)) = scope.get("make") else {
+            panic!("make should be a function")
+        };
+        let made = make.call(vec![], &scope).unwrap();
+        assert_eq!(Arc::strong_count(&frame), count + 1);
+        let Value::Function(made) = made else {
+            panic!("make should return a function")
+        };
+        assert!(matches!(made.call(vec![], &scope), Ok(Value::Function(_))));
+    }
+
+    #[test]
+    fn test_calls_share_globals() {
+        // Each call must not copy the globals, which hold the whole collection
+        let items = (0..4000).collect::<Vec<i64>>();
+        let scope = run(&format!(
+            r#"
+            items = {:?}
+            double = func(x) {{ return x * 2 }}
+            total = 0
+            for items as item {{ total += double(item) }}
+            "#,
+            items
+        ))
+        .unwrap();
+        assert_eq!(scope.get("total"), Some(Value::Integer(3999 * 4000)));
+    }
+
+    #[test]
+    fn test_functions_see_later_globals() {
+        let scope = run(r#"
+            f = func() { return limit }
+            limit = 3
+            first = f()
+            limit = 4
+            second = f()
+        "#)
+        .unwrap();
+        assert_eq!(scope.get("first"), Some(Value::Integer(3)));
+        assert_eq!(scope.get("second"), Some(Value::Integer(4)));
+    }
+
+    #[test]
+    fn test_closures_capture_locals() {
+        let scope = run(r#"
+            adder = func(n) {
+                return func(x) { return x + n }
+            }
+            add2 = adder(2)
+            n = 100
+            result = add2(1)
+            bump = func() {
+                n = 1
+                return n
+            }
+            local = bump()
+        "#)
+        .unwrap();
+        assert_eq!(scope.get("result"), Some(Value::Integer(3)));
+        assert_eq!(scope.get("local"), Some(Value::Integer(1)));
+        assert_eq!(scope.get("n"), Some(Value::Integer(100)));
+    }
+
+    #[test]
+    fn test_variables_shadow_builtins() {
+        let scope = run(r#"
+            before = length("abc")
+            length = func(x) { return 0 }
+            after = length("abc")
+        "#)
+        .unwrap();
+        assert_eq!(scope.get("before"), Some(Value::Integer(3)));
+        assert_eq!(scope.get("after"), Some(Value::Integer(0)));
+    }
+
+    #[test]
+    fn test_user_defined_functions() {
+        let scope = run(r#"
+            limit = 2
+            too_many = func(items) {
+                count = 0
+                for items as item { count = count + 1 }
+                return count > limit
+            }
+            a = too_many([1, 2])
+            b = too_many([1, 2, 3])
+        "#)
+        .unwrap();
+        assert_eq!(scope.get("a"), Some(Value::Boolean(false)));
+        assert_eq!(scope.get("b"), Some(Value::Boolean(true)));
+    }
+}
@@ -0,0 +1,17 @@
+mod builtins;
+mod error;
+mod evaluate;
+mod evaluation;
+mod execute;
+mod parameters;
+mod scope;
+mod value;
+
+pub(crate) use builtins::*;
+pub use error::*;
+pub(crate) use evaluate::*;
+pub use evaluation::*;
+pub(crate) use execute::*;
+pub use parameters::*;
+pub use scope::*;
+pub use value::*;
@@ -0,0 +1,130 @@
+use crate::parser::{Import, Param, Spanned};
+use crate::runtime::{Evaluate, RuntimeError, Scope, Value};
+use std::collections::HashMap;
+
+/// Parameter values and imports supplied by the host when evaluating a policy
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Parameters {
+    values: HashMap<String, Value>,
+    imports: HashMap<String, Value>,
+}
+
+impl Parameters {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Supply a value for the named parameter
+    pub fn with<T: Into<String>, V: Into<Value>>(mut self, name: T, value: V) -> Self {
+        self.insert(name, value);
+        self
+    }
+
+    pub fn insert<T: Into<String>, V: Into<Value>>(&mut self, name: T, value: V) {
+        self.values.insert(name.into(), value.into());
+    }
+
+    /// Supply the value of an import, by the name it is imported with, e.g.
+    /// `"tfplan/v2"`. The policy sees it under the import's binding.
+    pub fn with_import<T: Into<String>, V: Into<Value>>(mut self, name: T, value: V) -> Self {
+        self.imports.insert(name.into(), value.into());
+        self
+    }
+
+    /// Binds the declared imports and parameters into a new scope. Supplied values take
+    /// precedence over defaults, and defaults may refer to imports and to parameters
+    /// declared before them. Returns an error naming the first import or required
+    /// parameter that was not supplied.
+    pub fn bind(
+        &self,
+        imports: &[Spanned<Import>],
+        params: &[Spanned<Param>],
+    ) -> Result<Scope, RuntimeError> {
+        let mut scope = Scope::new();
+        for import in imports {
+            let value = self
+                .imports
+                .get(import.name.as_str())
+                .ok_or_else(|| RuntimeError::MissingImport(import.name.to_string()))?;
+            scope.set(import.binding(), value.clone());
+        }
+        for param in params {
+            let value = match (self.values.get(&param.name.0), &param.default) {
+                (Some(value), _) => value.clone(),
+                (None, Some(default)) => default.evaluate(&scope)?,
+                (None, None) => return Err(RuntimeError::MissingParameter(param.name.0.clone())),
+            };
+            scope.set(param.name.0.clone(), value);
+        }
+        Ok(scope)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_policy;
+
+    fn params(input: &str) -> Vec<Spanned<Param>> {
+        parse_policy(input).expect("policy should parse").params
+    }
+
+    #[test]
+    fn test_bind_defaults() {
+        let params = params("param limit default 2 * 5\nparam doubled default limit * 2");
+        let scope = Parameters::new().bind(&[], &params).unwrap();
+        assert_eq!(scope.get("limit"), Some(Value::Integer(10)));
+        assert_eq!(scope.get("doubled"), Some(Value::Integer(20)));
+    }
+
+    #[test]
+    fn test_bind_supplied() {
+        let params = params("param regions\nparam limit default 10");
+        let scope = Parameters::new()
+            .with("regions", vec!["us-east-1", "us-west-2"])
+            .with("limit", 3)
+            .bind(&[], &params)
+            .unwrap();
+        assert_eq!(
+            scope.get("regions"),
+            Some(Value::from(vec!["us-east-1", "us-west-2"]))
+        );
+        assert_eq!(scope.get("limit"), Some(Value::Integer(3)));
+    }
+
+    #[test]
+    fn test_missing_required_parameter() {
+        let params = params("param limit default 10\nparam regions");
+        let err = Parameters::new().bind(&[], &params).unwrap_err();
+        assert_eq!(err, RuntimeError::MissingParameter("regions".to_string()));
+        assert_eq!(
+            err.to_string(),
+            "missing value for required parameter `regions`"
+        );
+    }
+
+    #[test]
+    fn test_bind_imports() {
+        let policy =
+            parse_policy("import \"tfplan/v2\"\nimport \"strings\" as text\nparam plan default v2")
+                .unwrap();
+        let scope = Parameters::new()
+            .with_import("tfplan/v2", vec!["aws_instance"])
+            .with_import("strings", "helpers")
+            .bind(&policy.imports, &policy.params)
+            .unwrap();
+        assert_eq!(scope.get("v2"), Some(Value::from(vec!["aws_instance"])));
+        assert_eq!(scope.get("text"), Some(Value::from("helpers")));
+        assert_eq!(scope.get("plan"), Some(Value::from(vec!["aws_instance"])));
+
+        let err = Parameters::new()
+            .with_import("strings", "helpers")
+            .bind(&policy.imports, &policy.params)
+            .unwrap_err();
+        assert_eq!(err, RuntimeError::MissingImport("tfplan/v2".to_string()));
+        assert_eq!(
+            err.to_string(),
+            "no value supplied for import \"tfplan/v2\""
+        );
+    }
+}
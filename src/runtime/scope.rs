@@ -0,0 +1,207 @@
+use crate::runtime::Value;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Debug, Formatter};
+use std::sync::{Arc, PoisonError, RwLock};
+
+/// The variables visible to an expression while it is being evaluated. Inside a function
+/// call, assignments create local variables, while names that are not local are looked
+/// up among the globals of the policy. Scopes derived from this one share its globals
+/// rather than copying them.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Scope {
+    globals: Arc<HashMap<String, Value>>,
+    locals: Option<Arc<Frame>>,
+}
+
+/// A layer of local variables, such as the arguments of a function call. Names that are not in the frame are looked up in its parent.
+/// Frames are shared with the functions created in them, so a function sees variables
+/// assigned in its frame after it was created, including itself. A function stored in
+/// the frame it captured keeps that frame alive, so a call releases its frame when it
+/// returns, unless the returned value still needs it.
+#[derive(Default)]
+pub struct Frame {
+    variables: RwLock<HashMap<String, Value>>,
+    parent: Option<Arc<Frame>>,
+}
+
+impl Frame {
+    fn new(variables: HashMap<String, Value>, parent: Option<Arc<Frame>>) -> Self {
+        Frame {
+            variables: RwLock::new(variables),
+            parent,
+        }
+    }
+
+    /// Drops the variables of a frame that nothing can use anymore, which breaks the
+    /// cycle between the frame and any function stored in it that captured it. Does
+    /// nothing if `value` can still reach the frame through a function.
+    pub(crate) fn release(&self, value: &Value) {
+        if !self.reachable_from(value, &mut HashSet::new()) {
+            self.variables
+                .write()
+                .unwrap_or_else(PoisonError::into_inner)
+                .clear();
+        }
+    }
+
+    /// Whether a function in `value` captured this frame, directly or through the
+    /// frames and variables its closure leads to
+    fn reachable_from(&self, value: &Value, visited: &mut HashSet<*const Frame>) -> bool {
+        match value {
+            Value::List(items) => items.iter().any(|v| self.reachable_from(v, visited)),
+            Value::Map(entries) => entries
+                .iter()
+                .any(|(k, v)| self.reachable_from(k, visited) || self.reachable_from(v, visited)),
+            Value::Function(function) => {
+                let mut frame = function.closure.as_deref();
+                while let Some(current) = frame {
+                    if std::ptr::eq(self, current) {
+                        return true;
+                    }
+                    if !visited.insert(current) {
+                        return false;
+                    }
+                    let variables = current
+                        .variables
+                        .read()
+                        .unwrap_or_else(PoisonError::into_inner);
+                    if variables.values().any(|v| self.reachable_from(v, visited)) {
+                        return true;
+                    }
+                    frame = current.parent.as_deref();
+                }
+                false
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Frames are identified by where they live rather than by their variables
+impl PartialEq for Frame {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl Debug for Frame {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Frame").finish_non_exhaustive()
+    }
+}
+
+impl Scope {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, name: &str) -> Option<Value> {
+        self.lookup(name, Value::clone)
+    }
+
+    /// Whether a variable with the given name is visible from this scope
+    pub fn contains(&self, name: &str) -> bool {
+        self.lookup(name, |_| ()).is_some()
+    }
+
+    fn lookup<R>(&self, name: &str, f: impl FnOnce(&Value) -> R) -> Option<R> {
+        let mut frame = self.locals.as_deref();
+        while let Some(current) = frame {
+            let variables = current
+                .variables
+                .read()
+                .unwrap_or_else(PoisonError::into_inner);
+            if let Some(value) = variables.get(name) {
+                return Some(f(value));
+            }
+            frame = current.parent.as_deref();
+        }
+        self.globals.get(name).map(f)
+    }
+
+    pub fn set<T: Into<String>>(&mut self, name: T, value: Value) {
+        match &self.locals {
+            Some(locals) => locals
+                .variables
+                .write()
+                .unwrap_or_else(PoisonError::into_inner)
+                .insert(name.into(), value),
+            None => Arc::make_mut(&mut self.globals).insert(name.into(), value),
+        };
+    }
+
+    /// Removes a variable from the innermost layer of this scope, returning its value.
+    /// A variable of the same name in an outer layer becomes visible again.
+    pub(crate) fn remove(&mut self, name: &str) -> Option<Value> {
+        match &self.locals {
+            Some(locals) => locals
+                .variables
+                .write()
+                .unwrap_or_else(PoisonError::into_inner)
+                .remove(name),
+            None => Arc::make_mut(&mut self.globals).remove(name),
+        }
+    }
+
+    /// Create a scope that sees every variable of this one, with an additional variable
+    /// bound in a new layer of locals, so binding it never copies the variables already
+    /// in scope.
+    pub fn with<T: Into<String>>(&self, name: T, value: Value) -> Self {
+        Scope {
+            globals: Arc::clone(&self.globals),
+            locals: Some(Arc::new(Frame::new(
+                HashMap::from([(name.into(), value)]),
+                self.locals.clone(),
+            ))),
+        }
+    }
+
+    /// The frame of local variables, which a function captures when it is created.
+    /// Globals are not captured, as they are looked up when the function is called.
+    pub(crate) fn frame(&self) -> Option<Arc<Frame>> {
+        self.locals.clone()
+    }
+
+    /// Create the scope for a function call made from this scope. The call gets a frame
+    /// of its own on top of the one the function captured, and shares the globals.
+    pub(crate) fn call(&self, closure: Option<Arc<Frame>>) -> Self {
+        Scope {
+            globals: Arc::clone(&self.globals),
+            locals: Some(Arc::new(Frame::new(HashMap::new(), closure))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_shares_globals() {
+        let mut scope = Scope::new();
+        scope.set("items", Value::from(vec![1, 2, 3]));
+        let inner = scope
+            .with("x", Value::Integer(1))
+            .with("y", Value::Integer(2));
+        assert!(Arc::ptr_eq(&scope.globals, &inner.globals));
+        assert_eq!(inner.get("items"), Some(Value::from(vec![1, 2, 3])));
+        assert_eq!(inner.get("x"), Some(Value::Integer(1)));
+        assert_eq!(scope.get("x"), None);
+
+        let shadowed = inner.with("x", Value::Integer(3));
+        assert_eq!(shadowed.get("x"), Some(Value::Integer(3)));
+        assert_eq!(shadowed.get("y"), Some(Value::Integer(2)));
+        assert_eq!(inner.get("x"), Some(Value::Integer(1)));
+    }
+
+    #[test]
+    fn test_call_shares_globals() {
+        let mut scope = Scope::new();
+        scope.set("items", Value::from(vec![1, 2, 3]));
+        let mut call = scope.call(None);
+        call.set("items", Value::Integer(1));
+        assert!(Arc::ptr_eq(&scope.globals, &call.globals));
+        assert_eq!(call.get("items"), Some(Value::Integer(1)));
+        assert_eq!(scope.get("items"), Some(Value::from(vec![1, 2, 3])));
+    }
+}
@@ -0,0 +1,150 @@
+use crate::parser::{Identifier, Literal, Statement};
+use crate::runtime::{equals, Frame};
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+
+/// A value produced by evaluating an expression
+#[derive(Debug, PartialEq, Clone)]
+pub enum Value {
+    Null,
+    Undefined,
+    Integer(i64),
+    Float(f64),
+    /// Text. Indexing, slicing and `length()` count Unicode scalar values rather than
+    /// bytes, so `"née"[1:2]` is `"é"` and `length("née")` is 3.
+    String(Arc<String>),
+    Boolean(bool),
+    List(Vec<Value>),
+    Map(Vec<(Value, Value)>),
+    Function(Arc<Function>),
+}
+
+/// A function value created by evaluating a function literal. Functions capture the
+/// frame of local variables they were created in, while globals are looked up when they
+/// are called, so a function can call itself and use variables assigned after it was
+/// created.
+#[derive(Debug, PartialEq)]
+pub struct Function {
+    pub params: Vec<Identifier>,
+    pub body: Statement,
+    pub closure: Option<Arc<Frame>>,
+}
+
+impl Value {
+    /// Create a new string value
+    pub fn string<T: Into<String>>(s: T) -> Self {
+        Value::String(Arc::new(s.into()))
+    }
+
+    /// The name of the value's type, as used in error messages
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Null => "null",
+            Value::Undefined => "undefined",
+            Value::Integer(_) => "int",
+            Value::Float(_) => "float",
+            Value::String(_) => "string",
+            Value::Boolean(_) => "bool",
+            Value::List(_) => "list",
+            Value::Map(_) => "map",
+            Value::Function(_) => "func",
+        }
+    }
+
+    /// Looks up a key in a map value, returning `None` if the key is not present. Keys
+    /// compare as they do with `==`, so `1` and `1.0` are the same key.
+    pub fn get(&self, key: &Value) -> Option<&Value> {
+        match self {
+            Value::Map(entries) => entries.iter().find(|(k, _)| equals(k, key)).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+}
+
+/// Formats a value the way string interpolation inserts it. Strings are written as
+/// they are, except within lists and maps, where they are quoted.
+impl Display for Value {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::String(s) => write!(f, "{}", s),
+            value => write_nested(f, value),
+        }
+    }
+}
+
+fn write_nested(f: &mut Formatter<'_>, value: &Value) -> std::fmt::Result {
+    match value {
+        Value::Null => write!(f, "null"),
+        Value::Undefined => write!(f, "undefined"),
+        Value::Integer(i) => write!(f, "{}", i),
+        Value::Float(x) => write!(f, "{:?}", x),
+        Value::String(s) => write!(f, "{:?}", s),
+        Value::Boolean(b) => write!(f, "{}", b),
+        Value::List(items) => {
+            write!(f, "[")?;
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                write_nested(f, item)?;
+            }
+            write!(f, "]")
+        }
+        Value::Map(entries) => {
+            write!(f, "{{")?;
+            for (i, (key, value)) in entries.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                write_nested(f, key)?;
+                write!(f, ": ")?;
+                write_nested(f, value)?;
+            }
+            write!(f, "}}")
+        }
+        Value::Function(_) => write!(f, "func"),
+    }
+}
+
+impl From<Literal> for Value {
+    fn from(value: Literal) -> Self {
+        match value {
+            Literal::Null => Value::Null,
+            Literal::Undefined => Value::Undefined,
+            Literal::Integer(v) => Value::Integer(v),
+            Literal::Float(v) => Value::Float(v),
+            Literal::String(v) => Value::String(v),
+            Literal::Boolean(v) => Value::Boolean(v),
+        }
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Boolean(value)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Value::Integer(value)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value::Float(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::string(value)
+    }
+}
+
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(value: Vec<T>) -> Self {
+        Value::List(value.into_iter().map(Into::into).collect())
+    }
+}
@@ -0,0 +1,83 @@
+use warden_rs::{parse_policy, parse_policy_file, FileId, Parameters, RuntimeError, Value};
+
+const POLICY: &str = r#"
+param limit default 2
+param regions
+
+count = 0
+for regions as region {
+    count += 1
+}
+within_limit = rule { count <= limit }
+message = "${count} of ${limit}"
+main = rule { within_limit and message is not empty }
+"#;
+
+#[test]
+fn test_evaluate_policy() {
+    let policy = parse_policy(POLICY).unwrap();
+    let evaluation = policy
+        .evaluate(&Parameters::new().with("regions", vec!["us-east-1", "eu-west-1"]))
+        .unwrap();
+    assert_eq!(evaluation.passed(), Some(true));
+    assert_eq!(evaluation.rule("within_limit"), Some(&Value::Boolean(true)));
+    assert_eq!(
+        evaluation.globals.get("message"),
+        Some(Value::from("2 of 2"))
+    );
+
+    let evaluation = policy
+        .evaluate(
+            &Parameters::new()
+                .with("regions", vec!["us-east-1", "eu-west-1"])
+                .with("limit", 1),
+        )
+        .unwrap();
+    assert_eq!(evaluation.passed(), Some(false));
+    assert_eq!(
+        evaluation.rules,
+        vec![
+            ("within_limit".to_string(), Value::Boolean(false)),
+            ("main".to_string(), Value::Boolean(false)),
+        ]
+    );
+}
+
+#[test]
+fn test_evaluate_errors() {
+    let policy = parse_policy(POLICY).unwrap();
+    assert_eq!(
+        policy.evaluate(&Parameters::new()).unwrap_err(),
+        RuntimeError::MissingParameter("regions".to_string())
+    );
+    let policy = parse_policy("main = rule { missing }").unwrap();
+    assert_eq!(
+        policy.evaluate(&Parameters::new()).unwrap_err(),
+        RuntimeError::UndefinedVariable("missing".to_string())
+    );
+}
+
+#[test]
+fn test_evaluate_merged_policies() {
+    // Items run in the order they are listed, whatever file their spans point into
+    let mut policy = parse_policy_file(FileId(1), "\n\n\nlimit = 2").unwrap();
+    let rules = parse_policy_file(FileId(2), "main = rule { limit > 1 }").unwrap();
+    policy.items.extend(rules.items);
+    let evaluation = policy.evaluate(&Parameters::new()).unwrap();
+    assert_eq!(evaluation.passed(), Some(true));
+}
+
+#[test]
+fn test_evaluate_imports() {
+    let policy =
+        parse_policy("import \"tfplan/v2\" as tfplan\nmain = rule { \"aws_instance\" in tfplan }")
+            .unwrap();
+    let evaluation = policy
+        .evaluate(&Parameters::new().with_import("tfplan/v2", vec!["aws_instance"]))
+        .unwrap();
+    assert_eq!(evaluation.passed(), Some(true));
+    assert_eq!(
+        policy.evaluate(&Parameters::new()).unwrap_err(),
+        RuntimeError::MissingImport("tfplan/v2".to_string())
+    );
+}